@@ -6,6 +6,8 @@ use serde::{
 };
 use strum::Display;
 
+use crate::tasks::TaskId;
+
 // ANCHOR: action_enum
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
 pub enum Action {
@@ -29,5 +31,9 @@ pub enum Action {
   EnterProcessing,
   ExitProcessing,
   Update,
+  TaskProgress(TaskId, u8),
+  TaskCompleted(TaskId),
+  TaskFailed(TaskId, String),
+  TaskCancelled(TaskId),
 }
 // ANCHOR_END: action_enum
@@ -1,15 +1,24 @@
+use std::time::{Duration, Instant};
+
 use color_eyre::Result;
 use ratatui::{crossterm::event::KeyEvent, layout::Rect};
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
 use crate::{
   action::Action,
   components::{fps::FpsCounter, home::Home, Component},
   config::Config,
+  session::Session,
+  tasks::TaskManager,
   tui,
 };
 
+/// How long background tasks and components get to shut down before the terminal is restored.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+/// How often the session is saved while the application is running.
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Mode {
   #[default]
@@ -25,6 +34,7 @@ pub struct App {
   pub should_suspend: bool,
   pub mode: Mode,
   pub last_tick_key_events: Vec<KeyEvent>,
+  pub last_session_save: Instant,
 }
 
 impl App {
@@ -42,11 +52,13 @@ impl App {
       config,
       mode,
       last_tick_key_events: Vec::new(),
+      last_session_save: Instant::now(),
     })
   }
 
   pub async fn run(&mut self) -> Result<()> {
     let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+    let tasks = TaskManager::new(action_tx.clone());
 
     let mut tui = tui::Tui::new()?;
     tui.tick_rate(self.tick_rate);
@@ -57,10 +69,16 @@ impl App {
       component.register_action_handler(action_tx.clone())?;
     }
 
+    for component in self.components.iter_mut() {
+      component.register_task_manager(tasks.clone())?;
+    }
+
     for component in self.components.iter_mut() {
       component.register_config_handler(self.config.clone())?;
     }
 
+    self.restore_session();
+
     for component in self.components.iter_mut() {
       component.init()?;
     }
@@ -99,59 +117,128 @@ impl App {
         }
       }
 
-      while let Ok(action) = action_rx.try_recv() {
-        if action != Action::Tick && action != Action::Render {
-          log::debug!("{action:?}");
-        }
-        match action {
-          Action::Tick => {
-            self.last_tick_key_events.drain(..);
-          },
-          Action::Quit => self.should_quit = true,
-          Action::Suspend => self.should_suspend = true,
-          Action::Resume => self.should_suspend = false,
-          Action::Resize(w, h) => {
-            tui.resize(Rect::new(0, 0, w, h))?;
-            tui.draw(|frame| {
-              for component in self.components.iter_mut() {
-                let r = component.draw(frame, frame.area());
-                if let Err(e) = r {
-                  action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
-                }
-              }
-            })?;
-          },
-          Action::Render => {
-            tui.draw(|frame| {
-              for component in self.components.iter_mut() {
-                let r = component.draw(frame, frame.area());
-                if let Err(e) = r {
-                  action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
-                }
-              }
-            })?;
-          },
-          _ => {},
-        }
-        for component in self.components.iter_mut() {
-          if let Some(action) = component.update(action.clone())? {
-            action_tx.send(action)?
-          };
-        }
-      }
+      self.handle_actions(&mut tui, &action_tx, &mut action_rx)?;
       if self.should_suspend {
-        tui.suspend()?;
+        tui.suspend().await?;
         action_tx.send(Action::Resume)?;
         tui = tui::Tui::new()?;
         tui.tick_rate(self.tick_rate);
         tui.frame_rate(self.frame_rate);
         tui.enter()?;
       } else if self.should_quit {
-        tui.stop()?;
+        tasks.shutdown(SHUTDOWN_TIMEOUT).await;
+        tui.stop().await?;
+        // handle the actions sent by the tasks and components before they stopped, so that
+        // components can persist their state
+        self.handle_actions(&mut tui, &action_tx, &mut action_rx)?;
+        self.save_session();
+        self.shutdown().await;
         break;
       }
     }
-    tui.exit()?;
+    tui.exit().await?;
     Ok(())
   }
+
+  fn handle_actions(
+    &mut self,
+    tui: &mut tui::Tui,
+    action_tx: &UnboundedSender<Action>,
+    action_rx: &mut UnboundedReceiver<Action>,
+  ) -> Result<()> {
+    while let Ok(action) = action_rx.try_recv() {
+      if action != Action::Tick && action != Action::Render {
+        log::debug!("{action:?}");
+      }
+      match action {
+        Action::Tick => {
+          self.last_tick_key_events.drain(..);
+          if self.last_session_save.elapsed() >= SESSION_SAVE_INTERVAL {
+            self.save_session();
+          }
+        },
+        Action::Quit => self.should_quit = true,
+        Action::Suspend => self.should_suspend = true,
+        Action::Resume => self.should_suspend = false,
+        Action::Resize(w, h) => {
+          tui.resize(Rect::new(0, 0, w, h))?;
+          tui.draw(|frame| {
+            for component in self.components.iter_mut() {
+              let r = component.draw(frame, frame.area());
+              if let Err(e) = r {
+                action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
+              }
+            }
+          })?;
+        },
+        Action::Render => {
+          tui.draw(|frame| {
+            for component in self.components.iter_mut() {
+              let r = component.draw(frame, frame.area());
+              if let Err(e) = r {
+                action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
+              }
+            }
+          })?;
+        },
+        _ => {},
+      }
+      for component in self.components.iter_mut() {
+        if let Some(action) = component.update(action.clone())? {
+          action_tx.send(action)?
+        };
+      }
+    }
+    Ok(())
+  }
+
+  /// Restores the mode and the state of the components from the last session.
+  fn restore_session(&mut self) {
+    let mut session = Session::load();
+    self.mode = session.mode;
+    for component in self.components.iter_mut() {
+      let Some(key) = component.session_key() else {
+        continue;
+      };
+      if let Some(state) = session.components.remove(key) {
+        if let Err(e) = component.restore_state(state) {
+          log::warn!("Failed to restore the state of {key}: {e:?}");
+        }
+      }
+    }
+  }
+
+  fn save_session(&mut self) {
+    self.last_session_save = Instant::now();
+    let mut session = Session { mode: self.mode, ..Session::default() };
+    for component in self.components.iter() {
+      let Some(key) = component.session_key() else {
+        continue;
+      };
+      match component.save_state() {
+        Ok(state) => {
+          session.components.insert(key.to_string(), state);
+        },
+        Err(e) => log::error!("Failed to save the state of {key}: {e:?}"),
+      }
+    }
+    if let Err(e) = session.save() {
+      log::error!("Failed to save the session: {e:?}");
+    }
+  }
+
+  /// Runs the components' shutdown hooks, giving up after `SHUTDOWN_TIMEOUT`.
+  async fn shutdown(&mut self) {
+    let components = &mut self.components;
+    let shutdown = async move {
+      for component in components.iter_mut() {
+        if let Err(e) = component.on_shutdown().await {
+          log::error!("Failed to shut down component: {e:?}");
+        }
+      }
+    };
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown).await.is_err() {
+      log::error!("Components did not shut down within {SHUTDOWN_TIMEOUT:?}");
+    }
+  }
 }
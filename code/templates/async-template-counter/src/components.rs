@@ -1,12 +1,14 @@
 use color_eyre::eyre::Result;
+use futures::future::BoxFuture;
 use ratatui::{
   crossterm::event::{KeyEvent, MouseEvent},
   layout::Rect,
   Frame,
 };
+use serde_json::Value;
 use tokio::sync::mpsc::UnboundedSender;
 
-use crate::{action::Action, config::Config, tui::Event};
+use crate::{action::Action, config::Config, tasks::TaskManager, tui::Event};
 
 pub mod fps;
 pub mod home;
@@ -18,6 +20,10 @@ pub trait Component {
     Ok(())
   }
   #[allow(unused_variables)]
+  fn register_task_manager(&mut self, tasks: TaskManager) -> Result<()> {
+    Ok(())
+  }
+  #[allow(unused_variables)]
   fn register_config_handler(&mut self, config: Config) -> Result<()> {
     Ok(())
   }
@@ -45,5 +51,22 @@ pub trait Component {
     Ok(None)
   }
   fn draw(&mut self, f: &mut Frame, rect: Rect) -> Result<()>;
+  /// The key under which the state of the component is saved in the session, if it has any.
+  fn session_key(&self) -> Option<&'static str> {
+    None
+  }
+  /// The state to save in the session, restored with `restore_state` on the next start.
+  fn save_state(&self) -> Result<Value> {
+    Ok(Value::Null)
+  }
+  /// Restores the state saved by `save_state`. An error leaves the component in its default state.
+  #[allow(unused_variables)]
+  fn restore_state(&mut self, state: Value) -> Result<()> {
+    Ok(())
+  }
+  /// Called after the pending actions have been handled and before the terminal is restored.
+  fn on_shutdown(&mut self) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async { Ok(()) })
+  }
 }
 // ANCHOR_END: component
@@ -10,14 +10,16 @@ use ratatui::{
   widgets::{Block, BorderType, Borders, Clear, Paragraph, Row, Table},
   Frame,
 };
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::trace;
 use tui_input::{backend::crossterm::EventHandler, Input};
 
 use super::Component;
-use crate::{action::Action, config::key_event_to_string};
+use crate::{action::Action, config::key_event_to_string, tasks::TaskManager};
 
-#[derive(Default, Copy, Clone, PartialEq, Eq)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Mode {
   #[default]
   Normal,
@@ -25,6 +27,15 @@ pub enum Mode {
   Processing,
 }
 
+/// The part of [`Home`] that is saved between sessions.
+#[derive(Debug, Serialize, Deserialize)]
+struct HomeState {
+  counter: usize,
+  mode: Mode,
+  input: String,
+  text: Vec<String>,
+}
+
 #[derive(Default)]
 pub struct Home {
   pub show_help: bool,
@@ -34,6 +45,7 @@ pub struct Home {
   pub mode: Mode,
   pub input: Input,
   pub action_tx: Option<UnboundedSender<Action>>,
+  pub tasks: Option<TaskManager>,
   pub keymap: HashMap<KeyEvent, Action>,
   pub text: Vec<String>,
   pub last_events: Vec<KeyEvent>,
@@ -64,26 +76,40 @@ impl Home {
     self.text.push(s)
   }
 
+  // ANCHOR: schedule
   pub fn schedule_increment(&mut self, i: usize) {
-    let tx = self.action_tx.clone().unwrap();
-    tokio::spawn(async move {
-      tx.send(Action::EnterProcessing).unwrap();
-      tokio::time::sleep(Duration::from_secs(1)).await;
-      tx.send(Action::Increment(i)).unwrap();
-      tx.send(Action::ExitProcessing).unwrap();
-    });
+    self.schedule("increment", Action::Increment(i));
   }
 
   pub fn schedule_decrement(&mut self, i: usize) {
-    let tx = self.action_tx.clone().unwrap();
-    tokio::spawn(async move {
-      tx.send(Action::EnterProcessing).unwrap();
-      tokio::time::sleep(Duration::from_secs(1)).await;
-      tx.send(Action::Decrement(i)).unwrap();
-      tx.send(Action::ExitProcessing).unwrap();
+    self.schedule("decrement", Action::Decrement(i));
+  }
+
+  /// Sends `action` after a second of simulated work, reporting progress along the way.
+  fn schedule(&mut self, name: &str, action: Action) {
+    let Some(tasks) = &self.tasks else {
+      return;
+    };
+    self.mode = Mode::Processing;
+    tasks.spawn(name, |context| async move {
+      for step in 1..=10 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        context.progress(step * 10);
+      }
+      context.send(action);
+      Ok(())
     });
   }
 
+  /// Leaves the processing mode once the last scheduled task has finished.
+  fn task_finished(&mut self) {
+    let running = self.tasks.as_ref().map_or(0, |tasks| tasks.running().len());
+    if running == 0 && self.mode == Mode::Processing {
+      self.mode = Mode::Normal;
+    }
+  }
+  // ANCHOR_END: schedule
+
   pub fn increment(&mut self, i: usize) {
     self.counter = self.counter.saturating_add(i);
   }
@@ -99,6 +125,35 @@ impl Component for Home {
     Ok(())
   }
 
+  fn register_task_manager(&mut self, tasks: TaskManager) -> Result<()> {
+    self.tasks = Some(tasks);
+    Ok(())
+  }
+
+  fn session_key(&self) -> Option<&'static str> {
+    Some("home")
+  }
+
+  fn save_state(&self) -> Result<Value> {
+    let state = HomeState {
+      counter: self.counter,
+      // the scheduled tasks are not restored, so don't come back stuck in processing
+      mode: if self.mode == Mode::Processing { Mode::Normal } else { self.mode },
+      input: self.input.value().to_string(),
+      text: self.text.clone(),
+    };
+    Ok(serde_json::to_value(state)?)
+  }
+
+  fn restore_state(&mut self, state: Value) -> Result<()> {
+    let state: HomeState = serde_json::from_value(state)?;
+    self.counter = state.counter;
+    self.mode = state.mode;
+    self.input = Input::new(state.input);
+    self.text = state.text;
+    Ok(())
+  }
+
   fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
     self.last_events.push(key);
     let action = match self.mode {
@@ -122,6 +177,7 @@ impl Component for Home {
     Ok(Some(action))
   }
 
+  // ANCHOR: update
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     match action {
       Action::Tick => self.tick(),
@@ -132,6 +188,7 @@ impl Component for Home {
       Action::Increment(i) => self.increment(i),
       Action::Decrement(i) => self.decrement(i),
       Action::CompleteInput(s) => self.add(s),
+      Action::TaskCompleted(_) | Action::TaskFailed(..) | Action::TaskCancelled(_) => self.task_finished(),
       Action::EnterNormal => {
         self.mode = Mode::Normal;
       },
@@ -149,6 +206,7 @@ impl Component for Home {
     }
     Ok(None)
   }
+  // ANCHOR_END: update
 
   fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
     let rects = Layout::default().constraints([Constraint::Percentage(100), Constraint::Min(3)].as_ref()).split(rect);
@@ -159,6 +217,12 @@ impl Component for Home {
     text.insert(0, "".into());
     text.insert(0, format!("Render Ticker: {}", self.render_ticker).into());
     text.insert(0, format!("App Ticker: {}", self.app_ticker).into());
+    if let Some(tasks) = &self.tasks {
+      for task in tasks.running().iter().rev() {
+        let line = format!("{} #{}: {}%", task.name, task.id, task.progress);
+        text.insert(0, line.yellow().into());
+      }
+    }
     text.insert(0, format!("Counter: {}", self.counter).into());
     text.insert(0, "".into());
     text.insert(
@@ -245,11 +309,8 @@ impl Component for Home {
     f.render_widget(
       Block::default()
         .title(
-          Line::from(format!(
-            "{:?}",
-            &self.last_events.iter().map(key_event_to_string).collect::<Vec<_>>()
-          ))
-          .alignment(Alignment::Right),
+          Line::from(format!("{:?}", &self.last_events.iter().map(key_event_to_string).collect::<Vec<_>>()))
+            .alignment(Alignment::Right),
         )
         .title_style(Style::default().add_modifier(Modifier::BOLD)),
       Rect { x: rect.x + 1, y: rect.height.saturating_sub(1), width: rect.width.saturating_sub(2), height: 1 },
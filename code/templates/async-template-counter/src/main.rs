@@ -8,6 +8,8 @@ pub mod app;
 pub mod cli;
 pub mod components;
 pub mod config;
+pub mod session;
+pub mod tasks;
 pub mod tui;
 pub mod utils;
 
@@ -0,0 +1,203 @@
+use std::{
+  collections::BTreeMap,
+  future::Future,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex, MutexGuard, PoisonError,
+  },
+  time::{Duration, Instant},
+};
+
+use color_eyre::eyre::Result;
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::action::Action;
+
+pub type TaskId = u64;
+
+/// A snapshot of a running task, as returned by [`TaskManager::running`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskInfo {
+  pub id: TaskId,
+  pub name: String,
+  pub started: Instant,
+  /// The last reported progress, in percent.
+  pub progress: u8,
+}
+
+struct Task {
+  info: TaskInfo,
+  token: CancellationToken,
+  handle: Option<JoinHandle<()>>,
+}
+
+type Tasks = Arc<Mutex<BTreeMap<TaskId, Task>>>;
+
+fn lock(tasks: &Tasks) -> MutexGuard<'_, BTreeMap<TaskId, Task>> {
+  tasks.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Spawns background tasks on behalf of components and keeps track of them.
+///
+/// Every task gets a child of the manager's cancellation token, so cancelling a single task or
+/// shutting down the manager stops it at its next `.await`. When a task finishes, the manager sends
+/// `TaskCompleted`, `TaskFailed` or `TaskCancelled`, and tasks report progress through their
+/// [`TaskContext`] as `TaskProgress`.
+#[derive(Clone)]
+pub struct TaskManager {
+  tasks: Tasks,
+  next_id: Arc<AtomicU64>,
+  token: CancellationToken,
+  action_tx: UnboundedSender<Action>,
+}
+
+/// The handle given to a task to report progress and send actions.
+#[derive(Clone)]
+pub struct TaskContext {
+  pub id: TaskId,
+  pub token: CancellationToken,
+  tasks: Tasks,
+  action_tx: UnboundedSender<Action>,
+}
+
+impl TaskContext {
+  /// Sends an action to the application. Sending after the application has quit is a no-op.
+  pub fn send(&self, action: Action) {
+    let _ = self.action_tx.send(action);
+  }
+
+  /// Records the progress of the task, in percent, and sends it as `Action::TaskProgress`.
+  pub fn progress(&self, percent: u8) {
+    let percent = percent.min(100);
+    if let Some(task) = lock(&self.tasks).get_mut(&self.id) {
+      task.info.progress = percent;
+    }
+    self.send(Action::TaskProgress(self.id, percent));
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.token.is_cancelled()
+  }
+}
+
+impl TaskManager {
+  pub fn new(action_tx: UnboundedSender<Action>) -> Self {
+    Self { tasks: Arc::default(), next_id: Arc::default(), token: CancellationToken::new(), action_tx }
+  }
+
+  /// Spawns a named task and returns its id.
+  ///
+  /// The task is dropped at its next `.await` when it is cancelled, so it does not need to check
+  /// the token itself unless it does blocking work.
+  pub fn spawn<F, Fut>(&self, name: impl Into<String>, task: F) -> TaskId
+  where
+    F: FnOnce(TaskContext) -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+  {
+    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+    let token = self.token.child_token();
+    let context =
+      TaskContext { id, token: token.clone(), tasks: self.tasks.clone(), action_tx: self.action_tx.clone() };
+    let future = task(context);
+    let info = TaskInfo { id, name: name.into(), started: Instant::now(), progress: 0 };
+    log::debug!("Spawning task {id} ({})", info.name);
+
+    // hold the lock until the task is registered, so that a task that finishes immediately cannot
+    // try to remove itself before it was added
+    let mut tasks = lock(&self.tasks);
+    let handle = tokio::spawn({
+      let tasks = self.tasks.clone();
+      let action_tx = self.action_tx.clone();
+      let token = token.clone();
+      async move {
+        let result = tokio::select! {
+          _ = token.cancelled() => None,
+          result = future => Some(result),
+        };
+        lock(&tasks).remove(&id);
+        let action = match result {
+          Some(Ok(())) => Action::TaskCompleted(id),
+          Some(Err(err)) => {
+            log::error!("Task {id} failed: {err:?}");
+            Action::TaskFailed(id, err.to_string())
+          },
+          None => Action::TaskCancelled(id),
+        };
+        let _ = action_tx.send(action);
+      }
+    });
+    tasks.insert(id, Task { info, token, handle: Some(handle) });
+    id
+  }
+
+  /// Returns the tasks that are still running, oldest first.
+  pub fn running(&self) -> Vec<TaskInfo> {
+    lock(&self.tasks).values().map(|task| task.info.clone()).collect()
+  }
+
+  pub fn cancel(&self, id: TaskId) {
+    if let Some(task) = lock(&self.tasks).get(&id) {
+      task.token.cancel();
+    }
+  }
+
+  pub fn cancel_all(&self) {
+    for task in lock(&self.tasks).values() {
+      task.token.cancel();
+    }
+  }
+
+  /// Cancels all tasks and waits up to `timeout` for them to finish, aborting the ones that don't.
+  ///
+  /// No new tasks can be spawned after the manager was shut down: they are cancelled immediately.
+  pub async fn shutdown(&self, timeout: Duration) {
+    self.token.cancel();
+    let handles: Vec<JoinHandle<()>> = lock(&self.tasks).values_mut().filter_map(|task| task.handle.take()).collect();
+    if handles.is_empty() {
+      return;
+    }
+    let aborts: Vec<_> = handles.iter().map(JoinHandle::abort_handle).collect();
+    if tokio::time::timeout(timeout, futures::future::join_all(handles)).await.is_err() {
+      log::error!("Tasks did not finish within {timeout:?}, aborting them");
+      for abort in aborts {
+        abort.abort();
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use tokio::sync::mpsc;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_task_reports_progress_and_completion() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let manager = TaskManager::new(tx);
+    let id = manager.spawn("work", |context| async move {
+      context.progress(50);
+      Ok(())
+    });
+    assert_eq!(rx.recv().await, Some(Action::TaskProgress(id, 50)));
+    assert_eq!(rx.recv().await, Some(Action::TaskCompleted(id)));
+    assert!(manager.running().is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_shutdown_cancels_running_tasks() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let manager = TaskManager::new(tx);
+    let id = manager.spawn("sleep", |_| async move {
+      tokio::time::sleep(Duration::from_secs(60)).await;
+      Ok(())
+    });
+    assert_eq!(manager.running().len(), 1);
+    manager.shutdown(Duration::from_secs(1)).await;
+    assert_eq!(rx.recv().await, Some(Action::TaskCancelled(id)));
+    assert!(manager.running().is_empty());
+  }
+}
@@ -21,6 +21,9 @@ use tokio::{
 };
 use tokio_util::sync::CancellationToken;
 
+/// How long [`Tui::stop`] waits for the event loop to finish before aborting it.
+const STOP_TIMEOUT: Duration = Duration::from_millis(100);
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Event {
   Init,
@@ -129,19 +132,16 @@ impl Tui {
     });
   }
 
-  pub fn stop(&self) -> Result<()> {
+  /// Stops the event loop, waiting up to `STOP_TIMEOUT` for it to finish before aborting it.
+  pub async fn stop(&mut self) -> Result<()> {
     self.cancel();
-    let mut counter = 0;
-    while !self.task.is_finished() {
-      std::thread::sleep(Duration::from_millis(1));
-      counter += 1;
-      if counter > 50 {
-        self.task.abort();
-      }
-      if counter > 100 {
-        log::error!("Failed to abort task in 100 milliseconds for unknown reason");
-        break;
-      }
+    if self.task.is_finished() {
+      // stopping twice is fine, but a completed handle must not be awaited again
+      return Ok(());
+    }
+    if tokio::time::timeout(STOP_TIMEOUT, &mut self.task).await.is_err() {
+      log::error!("Event loop did not stop within {STOP_TIMEOUT:?}, aborting it");
+      self.task.abort();
     }
     Ok(())
   }
@@ -153,8 +153,13 @@ impl Tui {
     Ok(())
   }
 
-  pub fn exit(&mut self) -> Result<()> {
-    self.stop()?;
+  pub async fn exit(&mut self) -> Result<()> {
+    self.stop().await?;
+    self.restore()
+  }
+
+  /// Restores the terminal to its original state.
+  pub fn restore(&mut self) -> Result<()> {
     if crossterm::terminal::is_raw_mode_enabled()? {
       self.flush()?;
       crossterm::execute!(std::io::stderr(), LeaveAlternateScreen, cursor::Show)?;
@@ -167,8 +172,8 @@ impl Tui {
     self.cancellation_token.cancel();
   }
 
-  pub fn suspend(&mut self) -> Result<()> {
-    self.exit()?;
+  pub async fn suspend(&mut self) -> Result<()> {
+    self.exit().await?;
     #[cfg(not(windows))]
     signal_hook::low_level::raise(signal_hook::consts::signal::SIGTSTP)?;
     Ok(())
@@ -200,6 +205,37 @@ impl DerefMut for Tui {
 
 impl Drop for Tui {
   fn drop(&mut self) {
-    self.exit().unwrap();
+    // the event loop can't be awaited here, so abort it and only restore the terminal
+    self.cancel();
+    self.task.abort();
+    self.restore().unwrap();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ratatui::{layout::Rect, TerminalOptions, Viewport};
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_stop_twice() -> Result<()> {
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+    let options = TerminalOptions { viewport: Viewport::Fixed(Rect::new(0, 0, 10, 10)) };
+    let cancellation_token = CancellationToken::new();
+    let token = cancellation_token.clone();
+    let mut tui = Tui {
+      terminal: ratatui::Terminal::with_options(CrosstermBackend::new(stderr()), options)?,
+      task: tokio::spawn(async move { token.cancelled().await }),
+      cancellation_token,
+      event_rx,
+      event_tx,
+      frame_rate: 60.0,
+      tick_rate: 4.0,
+    };
+    tui.stop().await?;
+    assert!(tui.task.is_finished());
+    tui.stop().await?;
+    Ok(())
   }
 }
@@ -30,7 +30,7 @@ pub fn initialize_panic_handler() -> Result<()> {
   eyre_hook.install()?;
   std::panic::set_hook(Box::new(move |panic_info| {
     if let Ok(mut t) = crate::tui::Tui::new() {
-      if let Err(r) = t.exit() {
+      if let Err(r) = t.restore() {
         error!("Unable to exit Terminal: {:?}", r);
       }
     }
@@ -1,8 +1,13 @@
 // ANCHOR: all
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
-#[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize)]
+use crate::components::dialog::{DialogRequest, DialogResponse};
+
+#[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize, JsonSchema)]
 pub enum Action {
     Tick,
     Render,
@@ -13,5 +18,32 @@ pub enum Action {
     ClearScreen,
     Error(String),
     Help,
+    ToggleLogs,
+    ToggleProfiler,
+    ExportTrace,
+    Copy(String),
+    Paste,
+    Notify(Severity, String),
+    DismissNotification,
+    ToggleNotifications,
+    /// Show the screen registered under a name, with route parameters.
+    Navigate(String, BTreeMap<String, String>),
+    Back,
+    Forward,
+    OpenDialog(DialogRequest),
+    /// The answer to the dialog with the given id.
+    DialogResult(String, DialogResponse),
+    /// Sets the percentage shown by the progress dialog with the given id.
+    DialogProgress(String, u8),
+    CloseDialog(String),
+}
+
+/// The severity of a notification, which determines its color and how long it is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, Serialize, Deserialize, JsonSchema)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Success,
 }
 // ANCHOR_END: all
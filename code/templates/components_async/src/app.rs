@@ -1,49 +1,108 @@
 // ANCHOR: all
+use std::time::{Duration, Instant};
+
 use color_eyre::Result;
 use crossterm::event::KeyEvent;
-use ratatui::prelude::Rect;
+use ratatui::prelude::{Rect, Size};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use strum::VariantNames;
 use tokio::sync::mpsc;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
     action::Action,
-    components::{Component, fps::FpsCounter, home::Home},
+    clipboard::Clipboard,
+    components::{
+        Component,
+        dialog::Dialogs,
+        home::Home,
+        log_viewer::LogViewer,
+        notifications::Notifications,
+        profiler::{FrameTimings, Phase, Profiler},
+    },
     config::Config,
+    crash,
+    router::{Params, Router},
+    session::Session,
     tui::{Event, Tui},
 };
 
+/// How long the components get to shut down before the terminal is restored regardless.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+/// The route of the screen shown at start, unless the last session showed another one.
+const START_ROUTE: &str = "home";
+/// How often the session is saved while the application is running.
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
 pub struct App {
     config: Config,
     tick_rate: f64,
     frame_rate: f64,
+    /// Components that are shown on top of every screen.
     components: Vec<Box<dyn Component>>,
+    router: Router,
+    dialogs: Dialogs,
+    timings: FrameTimings,
+    clipboard: Clipboard,
     should_quit: bool,
     should_suspend: bool,
     mode: Mode,
     last_tick_key_events: Vec<KeyEvent>,
+    /// The saved component states that have not been restored yet, because their screen has not
+    /// been opened.
+    session: Session,
+    last_session_save: Instant,
     action_tx: mpsc::UnboundedSender<Action>,
     action_rx: mpsc::UnboundedReceiver<Action>,
 }
 
-#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(
+    Default,
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    VariantNames,
+)]
 pub enum Mode {
     #[default]
     Home,
+    Logs,
+    Dialog,
 }
 
 impl App {
-    pub fn new(tick_rate: f64, frame_rate: f64) -> Result<Self> {
+    pub fn new(tick_rate: f64, frame_rate: f64, config: Config) -> Result<Self> {
         let (action_tx, action_rx) = mpsc::unbounded_channel();
+        crash::record_config(&config);
+        let timings = FrameTimings::new(frame_rate);
+        let mut router = Router::new(action_tx.clone(), config.clone());
+        router.register("home", Mode::Home, |_| Ok(vec![Box::new(Home::new())]));
         Ok(Self {
             tick_rate,
             frame_rate,
-            components: vec![Box::new(Home::new()), Box::new(FpsCounter::default())],
+            components: vec![
+                Box::new(Profiler::new(timings.clone())),
+                Box::new(LogViewer::new()),
+                Box::new(Notifications::new()),
+            ],
+            router,
+            dialogs: Dialogs::new(),
+            timings,
+            clipboard: Clipboard::new(),
             should_quit: false,
             should_suspend: false,
-            config: Config::new()?,
+            config,
             mode: Mode::Home,
             last_tick_key_events: Vec::new(),
+            session: Session::default(),
+            last_session_save: Instant::now(),
             action_tx,
             action_rx,
         })
@@ -52,6 +111,7 @@ impl App {
     pub async fn run(&mut self) -> Result<()> {
         let mut tui = Tui::new()?
             // .mouse(true) // uncomment this line to enable mouse support
+            .paste(true)
             .tick_rate(self.tick_rate)
             .frame_rate(self.frame_rate);
         tui.enter()?;
@@ -65,30 +125,63 @@ impl App {
         for component in self.components.iter_mut() {
             component.init(tui.size()?)?;
         }
+        self.router.resize(tui.size()?);
+        self.restore_session()?;
+        self.mode = self.router.mode();
 
         let action_tx = self.action_tx.clone();
         loop {
             self.handle_events(&mut tui).await?;
             self.handle_actions(&mut tui)?;
             if self.should_suspend {
-                tui.suspend()?;
+                tui.suspend().await?;
                 action_tx.send(Action::Resume)?;
                 action_tx.send(Action::ClearScreen)?;
                 // tui.mouse(true);
                 tui.enter()?;
             } else if self.should_quit {
-                tui.stop()?;
+                self.shutdown(&mut tui).await?;
                 break;
             }
         }
-        tui.exit()?;
+        tui.exit().await?;
+        Ok(())
+    }
+
+    // ANCHOR: shutdown
+    /// Stops the event loop, handles the actions that are still queued so that components can
+    /// persist their state, and then runs the components' shutdown hooks.
+    async fn shutdown(&mut self, tui: &mut Tui) -> Result<()> {
+        tui.stop().await?;
+        self.handle_actions(tui)?;
+        self.save_session();
+        let components = self
+            .router
+            .all_components_mut()
+            .map(Box::as_mut)
+            .chain(self.components.iter_mut().map(Box::as_mut));
+        let shutdown = async move {
+            for component in components {
+                if let Err(err) = component.on_shutdown().await {
+                    error!("Failed to shut down {}: {err:?}", component.name());
+                }
+            }
+        };
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown)
+            .await
+            .is_err()
+        {
+            error!("Components did not shut down within {SHUTDOWN_TIMEOUT:?}");
+        }
         Ok(())
     }
+    // ANCHOR_END: shutdown
 
     async fn handle_events(&mut self, tui: &mut Tui) -> Result<()> {
         let Some(event) = tui.next_event().await else {
             return Ok(());
         };
+        crash::record_event(&event);
         let action_tx = self.action_tx.clone();
         match event {
             Event::Quit => action_tx.send(Action::Quit)?,
@@ -98,7 +191,15 @@ impl App {
             Event::Key(key) => self.handle_key_event(key)?,
             _ => {}
         }
-        for component in self.components.iter_mut() {
+        // while a dialog is open, it is the only component that receives events
+        if self.dialogs.is_open() {
+            if let Some(action) = self.dialogs.handle_events(Some(event))? {
+                action_tx.send(action)?;
+            }
+            return Ok(());
+        }
+        let components = active_components(&mut self.router, &mut self.components, None);
+        for component in components {
             if let Some(action) = component.handle_events(Some(event.clone()))? {
                 action_tx.send(action)?;
             }
@@ -108,7 +209,12 @@ impl App {
 
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
         let action_tx = self.action_tx.clone();
-        let Some(keymap) = self.config.keybindings.get(&self.mode) else {
+        let mode = if self.dialogs.is_open() {
+            Mode::Dialog
+        } else {
+            self.mode
+        };
+        let Some(keymap) = self.config.keybindings.get(&mode) else {
             return Ok(());
         };
         match keymap.get(&vec![key]) {
@@ -136,9 +242,13 @@ impl App {
             if action != Action::Tick && action != Action::Render {
                 debug!("{action:?}");
             }
+            crash::record_action(&action);
             match action {
                 Action::Tick => {
                     self.last_tick_key_events.drain(..);
+                    if self.last_session_save.elapsed() >= SESSION_SAVE_INTERVAL {
+                        self.save_session();
+                    }
                 }
                 Action::Quit => self.should_quit = true,
                 Action::Suspend => self.should_suspend = true,
@@ -146,10 +256,22 @@ impl App {
                 Action::ClearScreen => tui.terminal.clear()?,
                 Action::Resize(w, h) => self.handle_resize(tui, w, h)?,
                 Action::Render => self.render(tui)?,
+                Action::ToggleLogs => self.toggle_logs(),
+                Action::Copy(ref text) => self.copy(tui, text)?,
+                Action::Paste => self.paste(tui)?,
+                Action::Navigate(..) | Action::Back | Action::Forward => self.navigate(&action)?,
                 _ => {}
             }
-            for component in self.components.iter_mut() {
-                if let Some(action) = component.update(action.clone())? {
+            let components = active_components(
+                &mut self.router,
+                &mut self.components,
+                Some(&mut self.dialogs),
+            );
+            for component in components {
+                let start = Instant::now();
+                let result = component.update(action.clone())?;
+                self.timings.record(component.name(), Phase::Update, start);
+                if let Some(action) = result {
                     self.action_tx.send(action)?
                 };
             }
@@ -157,23 +279,186 @@ impl App {
         Ok(())
     }
 
+    /// Copies to the terminal's clipboard right away, and to the local clipboard in the
+    /// background.
+    fn copy(&mut self, tui: &mut Tui, text: &str) -> Result<()> {
+        if let Err(err) = self.clipboard.copy(tui.backend_mut(), text) {
+            error!("Failed to copy: {err:?}");
+            self.action_tx
+                .send(Action::Error(format!("Failed to copy: {err}")))?;
+        }
+        let clipboard = self.clipboard.clone();
+        let action_tx = self.action_tx.clone();
+        let text = text.to_string();
+        tokio::spawn(async move {
+            if let Err(err) = clipboard.copy_local(&text).await {
+                error!("Failed to copy: {err:?}");
+                let _ = action_tx.send(Action::Error(format!("Failed to copy: {err}")));
+            }
+        });
+        Ok(())
+    }
+
+    /// Reads the clipboard in the background and delivers its contents like a bracketed paste
+    /// from the terminal.
+    fn paste(&mut self, tui: &mut Tui) -> Result<()> {
+        let clipboard = self.clipboard.clone();
+        let action_tx = self.action_tx.clone();
+        let event_tx = tui.event_tx.clone();
+        tokio::spawn(async move {
+            // the app may have quit in the meantime, leaving no one to receive these
+            match clipboard.paste().await {
+                Ok(text) => {
+                    let _ = event_tx.send(Event::Paste(text));
+                }
+                Err(err) => {
+                    let _ = action_tx.send(Action::Error(format!("Failed to paste: {err}")));
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn navigate(&mut self, action: &Action) -> Result<()> {
+        let result = match action {
+            Action::Navigate(name, params) => self.router.navigate(name, params.clone()),
+            Action::Back => self.router.back().map(drop),
+            Action::Forward => self.router.forward().map(drop),
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            self.action_tx
+                .send(Action::Error(format!("Failed to navigate: {err}")))?;
+        }
+        debug!("Showing {:?}", self.router.current());
+        self.restore_components();
+        // the log viewer keeps its own mode while it is open
+        if self.mode != Mode::Logs {
+            self.mode = self.router.mode();
+        }
+        Ok(())
+    }
+
+    /// Shows the screen of the last session and restores the state of the components.
+    fn restore_session(&mut self) -> Result<()> {
+        self.session = Session::load();
+        let restored = match self.session.route.take() {
+            Some((name, params)) => match self.router.navigate(&name, params) {
+                Ok(()) => true,
+                Err(err) => {
+                    warn!("Failed to restore the screen {name}: {err:?}");
+                    false
+                }
+            },
+            None => false,
+        };
+        if !restored {
+            self.router.navigate(START_ROUTE, Params::new())?;
+        }
+        self.restore_components();
+        Ok(())
+    }
+
+    /// Restores the saved state of the shown components that have not got theirs yet.
+    fn restore_components(&mut self) {
+        let components = self
+            .router
+            .components_mut()
+            .chain(self.components.iter_mut());
+        for component in components {
+            let Some(key) = component.session_key() else {
+                continue;
+            };
+            if let Some(state) = self.session.components.remove(key)
+                && let Err(err) = component.restore_state(state)
+            {
+                warn!("Failed to restore the state of {key}: {err:?}");
+            }
+        }
+    }
+
+    /// Saves the shown screen and the state of the components, keeping the saved state of the
+    /// screens that were not opened in this session.
+    fn save_session(&mut self) {
+        self.last_session_save = Instant::now();
+        let mut session = Session {
+            route: self
+                .router
+                .current()
+                .map(|route| (route.name.clone(), route.params.clone())),
+            components: self.session.components.clone(),
+            ..Session::default()
+        };
+        let components = self
+            .router
+            .all_components_mut()
+            .chain(self.components.iter_mut());
+        for component in components {
+            let Some(key) = component.session_key() else {
+                continue;
+            };
+            match component.save_state() {
+                Ok(state) => {
+                    session.components.insert(key.to_string(), state);
+                }
+                Err(err) => error!("Failed to save the state of {key}: {err:?}"),
+            }
+        }
+        if let Err(err) = session.save() {
+            error!("Failed to save the session: {err:?}");
+        }
+    }
+
+    fn toggle_logs(&mut self) {
+        self.mode = match self.mode {
+            Mode::Logs => self.router.mode(),
+            _ => Mode::Logs,
+        };
+    }
+
     fn handle_resize(&mut self, tui: &mut Tui, w: u16, h: u16) -> Result<()> {
         tui.resize(Rect::new(0, 0, w, h))?;
+        self.router.resize(Size::new(w, h));
         self.render(tui)?;
         Ok(())
     }
 
     fn render(&mut self, tui: &mut Tui) -> Result<()> {
-        tui.draw(|frame| {
-            for component in self.components.iter_mut() {
-                if let Err(err) = component.draw(frame, frame.area()) {
+        let frame_start = Instant::now();
+        let completed_frame = tui.draw(|frame| {
+            let components = active_components(
+                &mut self.router,
+                &mut self.components,
+                Some(&mut self.dialogs),
+            );
+            for component in components {
+                let start = Instant::now();
+                let result = component.draw(frame, frame.area());
+                self.timings.record(component.name(), Phase::Draw, start);
+                if let Err(err) = result {
                     let _ = self
                         .action_tx
                         .send(Action::Error(format!("Failed to draw: {:?}", err)));
                 }
             }
         })?;
+        self.timings.end_frame(frame_start);
+        crash::record_frame(completed_frame.buffer);
         Ok(())
     }
 }
+
+/// The components of the active screen, followed by the overlays and the dialogs, in the order
+/// they are drawn.
+fn active_components<'a>(
+    router: &'a mut Router,
+    overlays: &'a mut [Box<dyn Component>],
+    dialogs: Option<&'a mut Dialogs>,
+) -> impl Iterator<Item = &'a mut (dyn Component + 'static)> {
+    router
+        .components_mut()
+        .map(Box::as_mut)
+        .chain(overlays.iter_mut().map(Box::as_mut))
+        .chain(dialogs.map(|dialogs| dialogs as &mut (dyn Component + 'static)))
+}
 // ANCHOR_END: all
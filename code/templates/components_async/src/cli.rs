@@ -1,7 +1,13 @@
 // ANCHOR: all
-use clap::Parser;
+use std::{collections::BTreeMap, fs, path::Path};
 
-use crate::config::{get_config_dir, get_data_dir};
+use clap::{Parser, Subcommand};
+use color_eyre::{Result, eyre::bail};
+
+use crate::config::{
+    CONFIG, Config, PROJECT_CONFIG_FILE, Provenance, existing_config_files, find_project_config,
+    get_config_dir, get_data_dir, get_system_config_dir, key_sequence_to_string,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
@@ -13,6 +19,151 @@ pub struct Cli {
     /// Frame rate, i.e. number of frames per second
     #[arg(short, long, value_name = "FLOAT", default_value_t = 60.0)]
     pub frame_rate: f64,
+
+    /// Override a config value, e.g. `--set data_dir=/tmp/data` (can be repeated)
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_override, global = true)]
+    pub overrides: Vec<(String, String)>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Commands that run without starting the TUI.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Inspect and manage the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Inspect the keybindings
+    #[command(subcommand)]
+    Keys(KeysCommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the config directory and the config files found in it
+    Path,
+    /// Print the effective configuration and where each value came from
+    Dump {
+        /// Print the merged configuration as JSON instead
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write the default configuration to the config directory
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check that the configuration can be loaded
+    Validate,
+    /// Print a JSON Schema for the config file, for editor validation and completion
+    Schema,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeysCommand {
+    /// Print the keybindings of each mode
+    List,
+}
+
+fn parse_override(raw: &str) -> Result<(String, String), String> {
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, found `{raw}`"))?;
+    Ok((key.trim().to_string(), value.to_string()))
+}
+
+impl Command {
+    pub fn run(self, overrides: &[(String, String)]) -> Result<()> {
+        match self {
+            Command::Config(ConfigCommand::Path) => print_config_path(),
+            Command::Config(ConfigCommand::Dump { json: true }) => {
+                let config = Config::with_overrides(overrides)?;
+                println!("{}", serde_json::to_string_pretty(&config)?);
+            }
+            Command::Config(ConfigCommand::Dump { json: false }) => {
+                let (_, provenance) = Config::load(overrides)?;
+                print_provenance(&provenance);
+            }
+            Command::Config(ConfigCommand::Init { force }) => init_config(force)?,
+            Command::Config(ConfigCommand::Validate) => {
+                Config::with_overrides(overrides)?;
+                println!("Configuration is valid");
+            }
+            Command::Config(ConfigCommand::Schema) => {
+                println!("{}", serde_json::to_string_pretty(&Config::json_schema())?);
+            }
+            Command::Keys(KeysCommand::List) => list_keys(&Config::with_overrides(overrides)?),
+        }
+        Ok(())
+    }
+}
+
+fn print_config_path() {
+    let print_files = |label: &str, directory: &Path| {
+        println!("{label}: {}", directory.display());
+        for (path, _) in existing_config_files(directory) {
+            println!("  {} (found)", path.display());
+        }
+    };
+    if let Some(system_dir) = get_system_config_dir() {
+        print_files("System", &system_dir);
+    }
+    print_files("User", &get_config_dir());
+    match find_project_config() {
+        Some(path) => println!("Project: {}", path.display()),
+        None => println!("Project: no {} found", PROJECT_CONFIG_FILE.as_str()),
+    }
+}
+
+fn print_provenance(provenance: &Provenance) {
+    let width = provenance
+        .values()
+        .map(|entry| entry.key.len())
+        .max()
+        .unwrap_or_default();
+    for entry in provenance.values() {
+        println!("{:width$} = {}  # {}", entry.key, entry.value, entry.source);
+    }
+}
+
+fn init_config(force: bool) -> Result<()> {
+    let config_dir = get_config_dir();
+    let path = config_dir.join("config.json5");
+    if path.exists() && !force {
+        bail!(
+            "{} already exists, use --force to overwrite it",
+            path.display()
+        );
+    }
+    fs::create_dir_all(&config_dir)?;
+    fs::write(&path, CONFIG)?;
+    println!("Wrote default configuration to {}", path.display());
+    Ok(())
+}
+
+fn list_keys(config: &Config) {
+    // sort the modes and keys so the output is stable
+    let modes: BTreeMap<String, BTreeMap<String, String>> = config
+        .keybindings
+        .iter()
+        .map(|(mode, bindings)| {
+            let bindings = bindings
+                .iter()
+                .map(|(keys, action)| (key_sequence_to_string(keys), action.to_string()))
+                .collect();
+            (format!("{mode:?}"), bindings)
+        })
+        .collect();
+    for (mode, bindings) in modes {
+        println!("{mode}");
+        let width = bindings.keys().map(String::len).max().unwrap_or_default();
+        for (keys, action) in bindings {
+            println!("  {keys:width$}  {action}");
+        }
+        println!();
+    }
 }
 
 const VERSION_MESSAGE: &str = concat!(
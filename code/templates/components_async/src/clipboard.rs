@@ -0,0 +1,192 @@
+use std::{env, io::Write, process::Stdio};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail, eyre},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    process::Command,
+};
+use tracing::debug;
+
+/// A clipboard command-line tool, with the arguments to copy and paste.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Provider {
+    copy: (&'static str, &'static [&'static str]),
+    paste: (&'static str, &'static [&'static str]),
+}
+
+const WAYLAND: Provider = Provider {
+    copy: ("wl-copy", &[]),
+    paste: ("wl-paste", &["--no-newline"]),
+};
+const XCLIP: Provider = Provider {
+    copy: ("xclip", &["-selection", "clipboard"]),
+    paste: ("xclip", &["-selection", "clipboard", "-out"]),
+};
+const XSEL: Provider = Provider {
+    copy: ("xsel", &["--clipboard", "--input"]),
+    paste: ("xsel", &["--clipboard", "--output"]),
+};
+const MACOS: Provider = Provider {
+    copy: ("pbcopy", &[]),
+    paste: ("pbpaste", &[]),
+};
+const WINDOWS: Provider = Provider {
+    copy: ("clip.exe", &[]),
+    paste: (
+        "powershell.exe",
+        &["-NoProfile", "-Command", "Get-Clipboard"],
+    ),
+};
+
+/// Copies text to the clipboard of the terminal and, when there is one, of the local machine.
+///
+/// Copying always writes an OSC 52 sequence to the terminal, which works over SSH in most modern
+/// terminals. When the application runs locally and a clipboard tool (`wl-copy`, `xclip`, `xsel`,
+/// `pbcopy` or `clip.exe`) is installed, the text is also handed to it, as some terminals ignore
+/// OSC 52. Reading the clipboard needs such a tool; otherwise the terminal's own paste shortcut
+/// still works through bracketed paste.
+///
+/// The tools are run asynchronously, as some of them take a while to start (`powershell.exe`) or
+/// wait for the owner of the selection (`xclip`), which must not block the event loop.
+#[derive(Debug, Clone, Default)]
+pub struct Clipboard {
+    provider: Option<Provider>,
+    tmux: bool,
+}
+
+impl Clipboard {
+    pub fn new() -> Self {
+        let remote = env::var_os("SSH_CONNECTION").is_some() || env::var_os("SSH_TTY").is_some();
+        let provider = if remote { None } else { detect_provider() };
+        debug!("Clipboard provider: {provider:?}");
+        Self {
+            provider,
+            tmux: env::var_os("TMUX").is_some(),
+        }
+    }
+
+    /// Writes `text` to the terminal's clipboard by sending the OSC 52 sequence to `writer` (the
+    /// terminal backend). Use [`Clipboard::copy_local`] to also copy it to the local clipboard.
+    pub fn copy(&self, writer: &mut impl Write, text: &str) -> Result<()> {
+        writer.write_all(osc52(text, self.tmux).as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Hands `text` to the local clipboard tool, if there is one.
+    pub async fn copy_local(&self, text: &str) -> Result<()> {
+        let Some(provider) = self.provider else {
+            return Ok(());
+        };
+        let (program, args) = provider.copy;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .wrap_err_with(|| format!("failed to run {program}"))?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| eyre!("failed to open stdin of {program}"))?;
+        stdin.write_all(text.as_bytes()).await?;
+        drop(stdin);
+        child.wait().await?;
+        Ok(())
+    }
+
+    /// Reads the text on the local clipboard.
+    pub async fn paste(&self) -> Result<String> {
+        let Some(provider) = self.provider else {
+            bail!("no clipboard tool available, use the terminal's paste shortcut instead");
+        };
+        let (program, args) = provider.paste;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .wrap_err_with(|| format!("failed to run {program}"))?;
+        let mut text = String::new();
+        if let Some(mut stdout) = child.stdout.take() {
+            stdout.read_to_string(&mut text).await?;
+        }
+        child.wait().await?;
+        // powershell terminates the output with a newline
+        if provider == WINDOWS {
+            text.truncate(text.trim_end_matches(['\r', '\n']).len());
+        }
+        Ok(text)
+    }
+}
+
+fn detect_provider() -> Option<Provider> {
+    let candidates = if cfg!(target_os = "macos") {
+        vec![MACOS]
+    } else if cfg!(windows) {
+        vec![WINDOWS]
+    } else {
+        let mut candidates = Vec::new();
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            candidates.push(WAYLAND);
+        }
+        if env::var_os("DISPLAY").is_some() {
+            candidates.extend([XCLIP, XSEL]);
+        }
+        // WSL can reach the Windows clipboard
+        candidates.push(WINDOWS);
+        candidates
+    };
+    candidates
+        .into_iter()
+        .find(|provider| is_installed(provider.copy.0) && is_installed(provider.paste.0))
+}
+
+fn is_installed(program: &str) -> bool {
+    let Some(path) = env::var_os("PATH") else {
+        return false;
+    };
+    env::split_paths(&path).any(|dir| dir.join(program).is_file())
+}
+
+/// Returns the OSC 52 sequence that sets the system clipboard to `text`.
+///
+/// Inside tmux the sequence is wrapped in a DCS passthrough so that it reaches the outer terminal.
+fn osc52(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(osc52("hello", false), "\x1b]52;c;aGVsbG8=\x07");
+        assert_eq!(
+            osc52("hello", true),
+            "\x1bPtmux;\x1b\x1b]52;c;aGVsbG8=\x07\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_copy_writes_osc52() {
+        let clipboard = Clipboard::default();
+        let mut output = Vec::new();
+        clipboard.copy(&mut output, "hi").unwrap();
+        assert_eq!(output, b"\x1b]52;c;aGk=\x07");
+    }
+}
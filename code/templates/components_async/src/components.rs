@@ -1,16 +1,22 @@
 // ANCHOR: all
 use color_eyre::Result;
 use crossterm::event::{KeyEvent, MouseEvent};
+use futures::future::BoxFuture;
 use ratatui::{
     Frame,
     layout::{Rect, Size},
 };
+use serde_json::Value;
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{action::Action, config::Config, tui::Event};
 
-pub mod fps;
+pub mod dialog;
 pub mod home;
+pub mod log_viewer;
+pub mod notifications;
+pub mod profiler;
+pub mod text_input;
 
 /// `Component` is a trait that represents a visual and interactive element of the user interface.
 ///
@@ -18,6 +24,15 @@ pub mod home;
 /// receive events, update state, and be rendered on the screen.
 // ANCHOR: component
 pub trait Component {
+    /// The name of the component, used to label its timings in the profiler.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name of the component type, without its module path.
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
     /// Register an action handler that can send actions for processing if necessary.
     ///
     /// # Arguments
@@ -70,6 +85,7 @@ pub trait Component {
         let action = match event {
             Some(Event::Key(key_event)) => self.handle_key_event(key_event)?,
             Some(Event::Mouse(mouse_event)) => self.handle_mouse_event(mouse_event)?,
+            Some(Event::Paste(text)) => self.handle_paste_event(text)?,
             _ => None,
         };
         Ok(action)
@@ -100,6 +116,22 @@ pub trait Component {
         let _ = mouse; // to appease clippy
         Ok(None)
     }
+    /// Handle pasted text and produce actions if necessary.
+    ///
+    /// Pasted text arrives both from the terminal's bracketed paste and from `Action::Paste`. Like
+    /// key events, it is sent to every component, so only the focused one should insert it.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The pasted text.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Action>>` - An action to be processed or none.
+    fn handle_paste_event(&mut self, text: String) -> Result<Option<Action>> {
+        let _ = text; // to appease clippy
+        Ok(None)
+    }
     /// Update the state of the component based on a received action. (REQUIRED)
     ///
     /// # Arguments
@@ -124,6 +156,49 @@ pub trait Component {
     ///
     /// * `Result<()>` - An Ok result or an error.
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()>;
+    /// The key under which the state of the component is saved in the session, if it has any.
+    ///
+    /// Components with the same key share their saved state, so give each stateful component a
+    /// key of its own.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&'static str>` - The session key, or none if the component has no state to save.
+    fn session_key(&self) -> Option<&'static str> {
+        None
+    }
+    /// The state to save in the session, restored with `restore_state` on the next start.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Value>` - The state of the component.
+    fn save_state(&self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+    /// Restore the state saved by `save_state`. An error leaves the component in its current state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state saved in the last session.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An Ok result or an error.
+    fn restore_state(&mut self, state: Value) -> Result<()> {
+        let _ = state; // to appease clippy
+        Ok(())
+    }
+    /// Clean up before the application exits, e.g. flush files or close connections.
+    ///
+    /// This is called after the pending actions have been handled and before the terminal is
+    /// restored. The whole shutdown is bounded by a timeout, so slow cleanup is cut short.
+    ///
+    /// # Returns
+    ///
+    /// * `BoxFuture<Result<()>>` - A future that completes when the cleanup is done.
+    fn on_shutdown(&mut self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
 }
 // ANCHOR_END: component
 // ANCHOR_END: all
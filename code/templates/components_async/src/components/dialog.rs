@@ -0,0 +1,460 @@
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{Component, text_input::TextInput};
+use crate::action::Action;
+
+/// A dialog to open with `Action::OpenDialog`.
+///
+/// The `id` is chosen by the caller and is sent back with the result in `Action::DialogResult`, so
+/// that the caller can tell its dialogs apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum DialogRequest {
+    /// Asks a yes/no question.
+    Confirm {
+        id: String,
+        title: String,
+        message: String,
+    },
+    /// Asks for a line of text.
+    Prompt {
+        id: String,
+        title: String,
+        message: String,
+        initial: String,
+    },
+    /// Asks to pick one option, or any number of options when `multiple` is set.
+    Select {
+        id: String,
+        title: String,
+        options: Vec<String>,
+        multiple: bool,
+    },
+    /// Shows the progress of some work, updated with `Action::DialogProgress` and closed with
+    /// `Action::CloseDialog`. Cancelling it sends `DialogResponse::Cancelled`.
+    Progress {
+        id: String,
+        title: String,
+        message: String,
+    },
+}
+
+/// The result of a dialog, sent as `Action::DialogResult`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum DialogResponse {
+    Confirmed(bool),
+    Text(String),
+    /// The indices of the selected options.
+    Selected(Vec<usize>),
+    Cancelled,
+}
+
+enum Body {
+    Confirm {
+        message: String,
+        yes: bool,
+    },
+    Prompt {
+        message: String,
+        input: TextInput,
+    },
+    Select {
+        options: Vec<String>,
+        multiple: bool,
+        checked: Vec<bool>,
+        state: ListState,
+    },
+    Progress {
+        message: String,
+        percent: u8,
+    },
+}
+
+struct Dialog {
+    id: String,
+    title: String,
+    body: Body,
+}
+
+/// What a key did to the top dialog.
+enum Outcome {
+    None,
+    Close(DialogResponse),
+}
+
+impl Dialog {
+    fn new(request: DialogRequest) -> Self {
+        let (id, title, body) = match request {
+            DialogRequest::Confirm { id, title, message } => (
+                id,
+                title,
+                Body::Confirm {
+                    message,
+                    yes: false,
+                },
+            ),
+            DialogRequest::Prompt {
+                id,
+                title,
+                message,
+                initial,
+            } => {
+                let input = TextInput::new().with_value(initial).focused(true);
+                (id, title, Body::Prompt { message, input })
+            }
+            DialogRequest::Select {
+                id,
+                title,
+                options,
+                multiple,
+            } => {
+                let checked = vec![false; options.len()];
+                let state = ListState::default().with_selected(Some(0));
+                let body = Body::Select {
+                    options,
+                    multiple,
+                    checked,
+                    state,
+                };
+                (id, title, body)
+            }
+            DialogRequest::Progress { id, title, message } => (
+                id,
+                title,
+                Body::Progress {
+                    message,
+                    percent: 0,
+                },
+            ),
+        };
+        Self { id, title, body }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> (Outcome, Option<Action>) {
+        if key.code == KeyCode::Esc {
+            return (Outcome::Close(DialogResponse::Cancelled), None);
+        }
+        let outcome = match &mut self.body {
+            Body::Confirm { yes, .. } => match key.code {
+                KeyCode::Char('y') => Outcome::Close(DialogResponse::Confirmed(true)),
+                KeyCode::Char('n') => Outcome::Close(DialogResponse::Confirmed(false)),
+                KeyCode::Left | KeyCode::Right | KeyCode::Tab | KeyCode::BackTab => {
+                    *yes = !*yes;
+                    Outcome::None
+                }
+                KeyCode::Enter => Outcome::Close(DialogResponse::Confirmed(*yes)),
+                _ => Outcome::None,
+            },
+            Body::Prompt { input, .. } => match key.code {
+                KeyCode::Enter if input.is_valid() => {
+                    Outcome::Close(DialogResponse::Text(input.value().to_string()))
+                }
+                _ => return (Outcome::None, input.handle_key(key)),
+            },
+            Body::Select {
+                options,
+                multiple,
+                checked,
+                state,
+            } => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    state.select_previous();
+                    Outcome::None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let last = options.len().saturating_sub(1);
+                    state.select(state.selected().map(|i| (i + 1).min(last)));
+                    Outcome::None
+                }
+                KeyCode::Char(' ') if *multiple => {
+                    if let Some(checked) = state.selected().and_then(|i| checked.get_mut(i)) {
+                        *checked = !*checked;
+                    }
+                    Outcome::None
+                }
+                KeyCode::Enter if *multiple => {
+                    let selected = (0..options.len()).filter(|i| checked[*i]).collect();
+                    Outcome::Close(DialogResponse::Selected(selected))
+                }
+                KeyCode::Enter => match state.selected() {
+                    Some(i) if i < options.len() => {
+                        Outcome::Close(DialogResponse::Selected(vec![i]))
+                    }
+                    _ => Outcome::None,
+                },
+                _ => Outcome::None,
+            },
+            Body::Progress { .. } => match key.code {
+                KeyCode::Enter => Outcome::Close(DialogResponse::Cancelled),
+                _ => Outcome::None,
+            },
+        };
+        (outcome, None)
+    }
+
+    fn height(&self) -> u16 {
+        let body = match &self.body {
+            Body::Confirm { .. } => 3,
+            Body::Prompt { .. } => 4,
+            Body::Select { options, .. } => (options.len() as u16).clamp(1, 10) + 1,
+            Body::Progress { .. } => 4,
+        };
+        body + 2
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let hint = match &self.body {
+            Body::Confirm { .. } => " y yes  n no  esc cancel ",
+            Body::Prompt { .. } => " enter ok  esc cancel ",
+            Body::Select { multiple: true, .. } => " space toggle  enter ok  esc cancel ",
+            Body::Select { .. } => " enter ok  esc cancel ",
+            Body::Progress { .. } => " esc cancel ",
+        };
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(format!(" {} ", self.title).bold())
+            .title_bottom(Line::from(hint).centered());
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        match &mut self.body {
+            Body::Confirm { message, yes } => {
+                let [message_area, buttons_area] =
+                    Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(inner);
+                let paragraph = Paragraph::new(message.as_str()).wrap(Wrap { trim: true });
+                frame.render_widget(paragraph, message_area);
+                let button = |label: &'static str, active: bool| {
+                    let style = if active {
+                        Style::new().reversed()
+                    } else {
+                        Style::new()
+                    };
+                    Span::styled(label, style)
+                };
+                let buttons = Line::from(vec![
+                    button(" Yes ", *yes),
+                    Span::raw("   "),
+                    button(" No ", !*yes),
+                ]);
+                frame.render_widget(buttons.centered(), buttons_area);
+            }
+            Body::Prompt { message, input } => {
+                let [message_area, input_area, error_area] = Layout::vertical([
+                    Constraint::Min(1),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                ])
+                .areas(inner);
+                frame.render_widget(Paragraph::new(message.as_str()), message_area);
+                input.draw(frame, input_area)?;
+                if let Some(error) = input.error() {
+                    frame.render_widget(Span::raw(error).red(), error_area);
+                }
+            }
+            Body::Select {
+                options,
+                multiple,
+                checked,
+                state,
+            } => {
+                let items = options.iter().zip(checked.iter()).map(|(option, checked)| {
+                    let mark = match (*multiple, *checked) {
+                        (false, _) => "",
+                        (true, true) => "[x] ",
+                        (true, false) => "[ ] ",
+                    };
+                    ListItem::new(format!("{mark}{option}"))
+                });
+                let list = List::new(items)
+                    .highlight_style(Style::new().reversed())
+                    .highlight_symbol("> ");
+                frame.render_stateful_widget(list, inner, state);
+            }
+            Body::Progress { message, percent } => {
+                let [message_area, gauge_area] =
+                    Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(inner);
+                frame.render_widget(Paragraph::new(message.as_str()), message_area);
+                let gauge = Gauge::default()
+                    .percent(u16::from(*percent))
+                    .gauge_style(Style::new().cyan());
+                frame.render_widget(gauge, gauge_area);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A stack of modal dialogs drawn above everything else.
+///
+/// Only the top dialog handles keys. The [`App`] sends all events to the dialogs while one is
+/// open and switches to `Mode::Dialog`, so the screens underneath don't see any input.
+///
+/// [`App`]: crate::app::App
+#[derive(Default)]
+pub struct Dialogs {
+    stack: Vec<Dialog>,
+}
+
+impl Dialogs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.stack.is_empty()
+    }
+
+    fn close(&mut self, id: &str) {
+        self.stack.retain(|dialog| dialog.id != id);
+    }
+}
+
+impl Component for Dialogs {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some(dialog) = self.stack.last_mut() else {
+            return Ok(None);
+        };
+        match dialog.handle_key(key) {
+            (Outcome::Close(response), _) => {
+                let id = dialog.id.clone();
+                self.stack.pop();
+                Ok(Some(Action::DialogResult(id, response)))
+            }
+            (Outcome::None, action) => Ok(action),
+        }
+    }
+
+    fn handle_paste_event(&mut self, text: String) -> Result<Option<Action>> {
+        match self.stack.last_mut().map(|dialog| &mut dialog.body) {
+            Some(Body::Prompt { input, .. }) => input.handle_paste_event(text),
+            _ => Ok(None),
+        }
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::OpenDialog(request) => self.stack.push(Dialog::new(request)),
+            Action::DialogProgress(id, value) => {
+                for dialog in self.stack.iter_mut().filter(|dialog| dialog.id == id) {
+                    if let Body::Progress { percent, .. } = &mut dialog.body {
+                        *percent = value.min(100);
+                    }
+                }
+            }
+            Action::CloseDialog(id) => self.close(&id),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        // each dialog is shifted a little so that the ones underneath remain visible
+        for (depth, dialog) in self.stack.iter_mut().enumerate() {
+            let offset = depth as u16;
+            let [area] = Layout::horizontal([Constraint::Length(60)])
+                .flex(Flex::Center)
+                .areas(area);
+            let [area] = Layout::vertical([Constraint::Length(dialog.height())])
+                .flex(Flex::Center)
+                .areas(area);
+            let area = Rect {
+                x: area.x + offset * 2,
+                y: area.y + offset,
+                ..area
+            }
+            .intersection(frame.area());
+            dialog.draw(frame, area)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn press(dialogs: &mut Dialogs, code: KeyCode) -> Option<Action> {
+        dialogs
+            .handle_key_event(KeyEvent::new(code, KeyModifiers::NONE))
+            .unwrap()
+    }
+
+    fn open(dialogs: &mut Dialogs, request: DialogRequest) {
+        dialogs.update(Action::OpenDialog(request)).unwrap();
+    }
+
+    #[test]
+    fn test_stacked_dialogs_answer_top_first() {
+        let mut dialogs = Dialogs::new();
+        open(
+            &mut dialogs,
+            DialogRequest::Confirm {
+                id: "quit".into(),
+                title: "Quit".into(),
+                message: "Really quit?".into(),
+            },
+        );
+        open(
+            &mut dialogs,
+            DialogRequest::Prompt {
+                id: "name".into(),
+                title: "Name".into(),
+                message: "Your name".into(),
+                initial: "Al".into(),
+            },
+        );
+        press(&mut dialogs, KeyCode::Char('y'));
+        assert_eq!(
+            press(&mut dialogs, KeyCode::Enter),
+            Some(Action::DialogResult(
+                "name".into(),
+                DialogResponse::Text("Aly".into())
+            ))
+        );
+        assert_eq!(
+            press(&mut dialogs, KeyCode::Char('n')),
+            Some(Action::DialogResult(
+                "quit".into(),
+                DialogResponse::Confirmed(false)
+            ))
+        );
+        assert!(!dialogs.is_open());
+    }
+
+    #[test]
+    fn test_multi_select() {
+        let mut dialogs = Dialogs::new();
+        open(
+            &mut dialogs,
+            DialogRequest::Select {
+                id: "pick".into(),
+                title: "Pick".into(),
+                options: vec!["a".into(), "b".into(), "c".into()],
+                multiple: true,
+            },
+        );
+        press(&mut dialogs, KeyCode::Char(' '));
+        press(&mut dialogs, KeyCode::Down);
+        press(&mut dialogs, KeyCode::Down);
+        press(&mut dialogs, KeyCode::Char(' '));
+        assert_eq!(
+            press(&mut dialogs, KeyCode::Enter),
+            Some(Action::DialogResult(
+                "pick".into(),
+                DialogResponse::Selected(vec![0, 2])
+            ))
+        );
+    }
+}
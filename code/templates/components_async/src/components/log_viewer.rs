@@ -0,0 +1,422 @@
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Margin, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Paragraph},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::Level;
+
+use super::{Component, text_input::TextInput};
+use crate::{
+    action::Action,
+    logging::{self, LogBuffer, LogEntry},
+};
+
+/// The text field currently being edited in the log viewer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Search,
+    Target,
+    Directives,
+}
+
+/// The filters of [`LogViewer`] that are saved between sessions.
+#[derive(Debug, Serialize, Deserialize)]
+struct LogViewerState {
+    /// The most verbose level shown, e.g. `INFO`.
+    min_level: String,
+    target: String,
+    search: String,
+    follow: bool,
+    show_spans: bool,
+}
+
+/// An overlay that shows the most recent log events of the application.
+pub struct LogViewer {
+    buffer: LogBuffer,
+    visible: bool,
+    min_level: Level,
+    target: String,
+    search: String,
+    follow: bool,
+    show_spans: bool,
+    /// Index of the first visible line when not following.
+    offset: usize,
+    /// The height of the list in the last frame, used for paging.
+    page_height: usize,
+    editing: Option<(Field, TextInput)>,
+    status: Option<String>,
+}
+
+impl Default for LogViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogViewer {
+    pub fn new() -> Self {
+        Self {
+            buffer: logging::LOG_BUFFER.clone(),
+            visible: false,
+            min_level: Level::TRACE,
+            target: String::new(),
+            search: String::new(),
+            follow: true,
+            show_spans: true,
+            offset: 0,
+            page_height: 0,
+            editing: None,
+            status: None,
+        }
+    }
+
+    fn matches(&self, entry: &LogEntry) -> bool {
+        entry.level <= self.min_level
+            && entry.target.contains(&self.target)
+            && (self.search.is_empty()
+                || entry.message.contains(&self.search)
+                || entry.fields.contains(&self.search))
+    }
+
+    fn filtered_entries(&self) -> Vec<LogEntry> {
+        self.buffer
+            .entries()
+            .into_iter()
+            .filter(|entry| self.matches(entry))
+            .collect()
+    }
+
+    fn cycle_level(&mut self) {
+        self.min_level = match self.min_level {
+            Level::TRACE => Level::ERROR,
+            Level::ERROR => Level::WARN,
+            Level::WARN => Level::INFO,
+            Level::INFO => Level::DEBUG,
+            Level::DEBUG => Level::TRACE,
+        };
+    }
+
+    fn scroll_up(&mut self, lines: usize) {
+        if self.follow {
+            self.follow = false;
+            let len = self.filtered_entries().len();
+            self.offset = len.saturating_sub(self.page_height);
+        }
+        self.offset = self.offset.saturating_sub(lines);
+    }
+
+    fn scroll_down(&mut self, lines: usize) {
+        if self.follow {
+            return;
+        }
+        let len = self.filtered_entries().len();
+        self.offset = self.offset.saturating_add(lines);
+        if self.offset + self.page_height >= len {
+            self.follow = true;
+        }
+    }
+
+    fn start_editing(&mut self, field: Field) {
+        let value = match field {
+            Field::Search => self.search.clone(),
+            Field::Target => self.target.clone(),
+            Field::Directives => logging::current_filter().unwrap_or_default(),
+        };
+        let input = TextInput::new().with_value(value).focused(true);
+        self.editing = Some((field, input));
+        self.status = None;
+    }
+
+    fn apply_edit(&mut self, field: Field, value: String) {
+        match field {
+            Field::Search => self.search = value,
+            Field::Target => self.target = value,
+            Field::Directives => {
+                self.status = Some(match logging::set_filter(&value) {
+                    Ok(()) => format!("Filter set to `{value}`"),
+                    Err(err) => format!("Invalid filter: {err}"),
+                });
+            }
+        }
+    }
+
+    fn handle_edit_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let (field, input) = self.editing.as_mut()?;
+        match key.code {
+            KeyCode::Enter => {
+                let (field, value) = (*field, input.value().to_string());
+                self.editing = None;
+                self.apply_edit(field, value);
+            }
+            KeyCode::Esc => self.editing = None,
+            _ => return input.handle_key(key),
+        }
+        None
+    }
+
+    /// Returns the entries that are currently on screen as plain text.
+    fn visible_text(&self) -> String {
+        self.filtered_entries()
+            .iter()
+            .skip(self.offset)
+            .take(self.page_height)
+            .map(|entry| {
+                self.render_entry(entry)
+                    .spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render_entry(&self, entry: &LogEntry) -> Line<'static> {
+        let level_color = match entry.level {
+            Level::ERROR => Color::Red,
+            Level::WARN => Color::Yellow,
+            Level::INFO => Color::Green,
+            Level::DEBUG => Color::Blue,
+            Level::TRACE => Color::Magenta,
+        };
+        let mut spans = vec![
+            Span::raw(entry.timestamp.format("%H:%M:%S%.3f ").to_string()).dim(),
+            Span::styled(format!("{:>5} ", entry.level), level_color),
+            Span::raw(format!("{}: ", entry.target)).dim(),
+        ];
+        if self.show_spans && !entry.spans.is_empty() {
+            spans.push(Span::raw(format!("{}: ", entry.spans.join(":"))).italic());
+        }
+        spans.extend(highlight(&entry.message, &self.search));
+        if !entry.fields.is_empty() {
+            spans.push(Span::raw(" "));
+            spans.extend(
+                highlight(&entry.fields, &self.search)
+                    .into_iter()
+                    .map(Stylize::dim),
+            );
+        }
+        Line::from(spans)
+    }
+
+    fn draw_status(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if let Some((field, input)) = &mut self.editing {
+            let label = match field {
+                Field::Search => "search: ",
+                Field::Target => "target: ",
+                Field::Directives => "filter: ",
+            };
+            let [label_area, input_area] =
+                Layout::horizontal([Constraint::Length(label.len() as u16), Constraint::Min(0)])
+                    .areas(area);
+            frame.render_widget(Span::raw(label).bold(), label_area);
+            return input.draw(frame, input_area);
+        }
+        frame.render_widget(Paragraph::new(self.status_line()), area);
+        Ok(())
+    }
+
+    fn status_line(&self) -> Line<'_> {
+        if let Some(status) = &self.status {
+            return Line::raw(status.as_str());
+        }
+        let follow = if self.follow { "on" } else { "off" };
+        Line::from(format!(
+            "level: {}+ | target: {:?} | search: {:?} | follow: {follow} | filter: {}",
+            self.min_level,
+            self.target,
+            self.search,
+            logging::current_filter().unwrap_or_default(),
+        ))
+        .dim()
+    }
+}
+
+/// Splits `text` into spans, highlighting every occurrence of `pattern`.
+fn highlight(text: &str, pattern: &str) -> Vec<Span<'static>> {
+    if pattern.is_empty() {
+        return vec![Span::raw(text.to_string())];
+    }
+    let mut spans = Vec::new();
+    let mut rest = text;
+    while let Some(index) = rest.find(pattern) {
+        let (before, matched) = rest.split_at(index);
+        if !before.is_empty() {
+            spans.push(Span::raw(before.to_string()));
+        }
+        spans.push(Span::styled(
+            pattern.to_string(),
+            Style::new().black().on_yellow(),
+        ));
+        rest = &matched[pattern.len()..];
+    }
+    if !rest.is_empty() {
+        spans.push(Span::raw(rest.to_string()));
+    }
+    spans
+}
+
+impl Component for LogViewer {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if !self.visible {
+            return Ok(None);
+        }
+        if self.editing.is_some() {
+            return Ok(self.handle_edit_key(key));
+        }
+        self.status = None;
+        match key.code {
+            KeyCode::Esc => return Ok(Some(Action::ToggleLogs)),
+            KeyCode::Char('/') => self.start_editing(Field::Search),
+            KeyCode::Char('t') => self.start_editing(Field::Target),
+            KeyCode::Char(':') => self.start_editing(Field::Directives),
+            KeyCode::Char('l') => self.cycle_level(),
+            KeyCode::Char('s') => self.show_spans = !self.show_spans,
+            KeyCode::Char('f') => self.follow = !self.follow,
+            KeyCode::Char('y') => return Ok(Some(Action::Copy(self.visible_text()))),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_up(1),
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_down(1),
+            KeyCode::PageUp => self.scroll_up(self.page_height),
+            KeyCode::PageDown => self.scroll_down(self.page_height),
+            KeyCode::Char('g') | KeyCode::Home => {
+                self.follow = false;
+                self.offset = 0;
+            }
+            KeyCode::Char('G') | KeyCode::End => self.follow = true,
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn handle_paste_event(&mut self, text: String) -> Result<Option<Action>> {
+        match (self.visible, self.editing.as_mut()) {
+            (true, Some((_, input))) => input.handle_paste_event(text),
+            _ => Ok(None),
+        }
+    }
+
+    fn session_key(&self) -> Option<&'static str> {
+        Some("log_viewer")
+    }
+
+    fn save_state(&self) -> Result<Value> {
+        let state = LogViewerState {
+            min_level: self.min_level.to_string(),
+            target: self.target.clone(),
+            search: self.search.clone(),
+            follow: self.follow,
+            show_spans: self.show_spans,
+        };
+        Ok(serde_json::to_value(state)?)
+    }
+
+    fn restore_state(&mut self, state: Value) -> Result<()> {
+        let state: LogViewerState = serde_json::from_value(state)?;
+        self.min_level = state.min_level.parse()?;
+        self.target = state.target;
+        self.search = state.search;
+        self.follow = state.follow;
+        self.show_spans = state.show_spans;
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if action == Action::ToggleLogs {
+            self.visible = !self.visible;
+            self.editing = None;
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+        let area = area.inner(Margin::new(2, 1));
+        frame.render_widget(Clear, area);
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(" Logs ")
+            .title_bottom(
+                Line::from(
+                    " / search  t target  l level  : filter  f follow  s spans  y copy  esc close ",
+                )
+                .centered(),
+            );
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [list_area, status_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner);
+        self.page_height = list_area.height as usize;
+        let entries = self.filtered_entries();
+        let max_offset = entries.len().saturating_sub(self.page_height);
+        if self.follow {
+            self.offset = max_offset;
+        }
+        self.offset = self.offset.min(max_offset);
+        let lines: Vec<Line> = entries
+            .iter()
+            .skip(self.offset)
+            .take(self.page_height)
+            .map(|entry| self.render_entry(entry))
+            .collect();
+        frame.render_widget(Paragraph::new(lines), list_area);
+        self.draw_status(frame, status_area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let style = Style::new().black().on_yellow();
+        assert_eq!(
+            highlight("foo bar foo", "foo"),
+            vec![
+                Span::styled("foo", style),
+                Span::raw(" bar "),
+                Span::styled("foo", style),
+            ]
+        );
+        assert_eq!(highlight("foo", ""), vec![Span::raw("foo")]);
+    }
+
+    #[test]
+    fn test_cycle_level() {
+        let mut viewer = LogViewer::new();
+        viewer.cycle_level();
+        assert_eq!(viewer.min_level, Level::ERROR);
+        viewer.cycle_level();
+        assert_eq!(viewer.min_level, Level::WARN);
+    }
+
+    #[test]
+    fn test_state_round_trip() -> Result<()> {
+        let mut viewer = LogViewer::new();
+        viewer.cycle_level();
+        viewer.apply_edit(Field::Search, "session".into());
+        viewer.apply_edit(Field::Target, "app".into());
+        viewer.follow = false;
+        let state = viewer.save_state()?;
+
+        let mut restored = LogViewer::new();
+        restored.restore_state(state)?;
+        assert_eq!(restored.min_level, Level::ERROR);
+        assert_eq!(restored.search, "session");
+        assert_eq!(restored.target, "app");
+        assert!(!restored.follow);
+        assert!(restored.show_spans);
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+use ratatui::{
+    Frame,
+    layout::{Margin, Rect},
+    style::{Color, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Paragraph, Wrap},
+};
+use unicode_width::UnicodeWidthStr;
+
+use super::Component;
+use crate::action::{Action, Severity};
+
+/// The maximum number of toasts on screen at once. Older ones are dismissed.
+const MAX_TOASTS: usize = 5;
+/// The number of notifications kept for the history panel.
+const HISTORY_LEN: usize = 100;
+const TOAST_WIDTH: u16 = 50;
+/// The maximum number of message lines shown in a toast.
+const TOAST_LINES: u16 = 4;
+
+struct Toast {
+    severity: Severity,
+    message: String,
+    shown: Instant,
+}
+
+struct Entry {
+    time: DateTime<Local>,
+    severity: Severity,
+    message: String,
+}
+
+/// Shows `Action::Notify` and `Action::Error` messages as toasts in the top right corner.
+///
+/// Toasts disappear after a timeout that depends on their severity, or when dismissed with
+/// `Action::DismissNotification`. `Action::ToggleNotifications` opens a panel with the past
+/// notifications.
+#[derive(Default)]
+pub struct Notifications {
+    toasts: VecDeque<Toast>,
+    history: VecDeque<Entry>,
+    show_history: bool,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn notify(&mut self, severity: Severity, message: String) {
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            severity,
+            message: message.clone(),
+            shown: Instant::now(),
+        });
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(Entry {
+            time: Local::now(),
+            severity,
+            message,
+        });
+    }
+
+    fn remove_expired(&mut self, now: Instant) {
+        self.toasts
+            .retain(|toast| now.duration_since(toast.shown) < timeout(toast.severity));
+    }
+
+    fn draw_toasts(&self, frame: &mut Frame, area: Rect) {
+        let area = area.inner(Margin::new(1, 1));
+        let width = TOAST_WIDTH.min(area.width);
+        let mut y = area.y;
+        // the newest toast is at the top
+        for toast in self.toasts.iter().rev() {
+            let text_width = toast.message.width() as u16;
+            let lines = text_width
+                .div_ceil(width.saturating_sub(2).max(1))
+                .clamp(1, TOAST_LINES);
+            let height = lines + 2;
+            if y + height > area.bottom() {
+                break;
+            }
+            let toast_area = Rect::new(area.right() - width, y, width, height);
+            let color = color(toast.severity);
+            let block = Block::bordered()
+                .border_type(BorderType::Rounded)
+                .border_style(color)
+                .title(Span::styled(format!(" {} ", toast.severity), color).bold());
+            let paragraph = Paragraph::new(toast.message.as_str())
+                .wrap(Wrap { trim: true })
+                .block(block);
+            frame.render_widget(Clear, toast_area);
+            frame.render_widget(paragraph, toast_area);
+            y += height;
+        }
+    }
+
+    fn draw_history(&self, frame: &mut Frame, area: Rect) {
+        let area = area.inner(Margin::new(4, 2));
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(" Notifications ")
+            .title_bottom(Line::from(" ctrl-n close ").centered());
+        let lines: Vec<Line> = self
+            .history
+            .iter()
+            .rev()
+            .map(|entry| {
+                Line::from(vec![
+                    Span::raw(entry.time.format("%H:%M:%S ").to_string()).dim(),
+                    Span::styled(format!("{:<8}", entry.severity), color(entry.severity)),
+                    Span::raw(entry.message.as_str()),
+                ])
+            })
+            .collect();
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+fn timeout(severity: Severity) -> Duration {
+    match severity {
+        Severity::Error => Duration::from_secs(10),
+        Severity::Warning => Duration::from_secs(6),
+        Severity::Info | Severity::Success => Duration::from_secs(3),
+    }
+}
+
+fn color(severity: Severity) -> Color {
+    match severity {
+        Severity::Error => Color::Red,
+        Severity::Warning => Color::Yellow,
+        Severity::Info => Color::Blue,
+        Severity::Success => Color::Green,
+    }
+}
+
+impl Component for Notifications {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Tick => self.remove_expired(Instant::now()),
+            Action::Error(message) => self.notify(Severity::Error, message),
+            Action::Notify(severity, message) => self.notify(severity, message),
+            Action::DismissNotification => {
+                self.toasts.pop_back();
+            }
+            Action::ToggleNotifications => self.show_history = !self.show_history,
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if self.show_history {
+            self.draw_history(frame, area);
+        }
+        self.draw_toasts(frame, area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_toasts_expire_and_are_kept_in_history() {
+        let mut notifications = Notifications::new();
+        notifications.notify(Severity::Info, "saved".into());
+        notifications.notify(Severity::Error, "failed".into());
+        notifications.remove_expired(Instant::now() + Duration::from_secs(5));
+        let messages: Vec<_> = notifications
+            .toasts
+            .iter()
+            .map(|toast| toast.message.as_str())
+            .collect();
+        assert_eq!(messages, ["failed"]);
+        assert_eq!(notifications.history.len(), 2);
+    }
+
+    #[test]
+    fn test_dismiss_removes_newest() {
+        let mut notifications = Notifications::new();
+        notifications
+            .update(Action::Notify(Severity::Warning, "first".into()))
+            .unwrap();
+        notifications
+            .update(Action::Error("second".into()))
+            .unwrap();
+        notifications.update(Action::DismissNotification).unwrap();
+        assert_eq!(notifications.toasts.len(), 1);
+        assert_eq!(notifications.toasts[0].message, "first");
+    }
+}
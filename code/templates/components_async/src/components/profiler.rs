@@ -0,0 +1,452 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use color_eyre::{Result, eyre::eyre};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Margin, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, BorderType, Clear, Paragraph, Row, Sparkline, SparklineBar,
+        Table,
+    },
+};
+use serde_json::json;
+
+use super::Component;
+use crate::{
+    action::{Action, Severity},
+    config,
+};
+
+/// The number of frames and component samples kept for the statistics.
+const SAMPLES: usize = 300;
+/// The number of spans kept for the trace export.
+const TRACE_SPANS: usize = 20_000;
+/// Upper bounds of the frame time histogram buckets, in milliseconds.
+const HISTOGRAM_BUCKETS: [u64; 7] = [1, 2, 4, 8, 16, 33, 66];
+
+/// What a component was doing while it was being timed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Update,
+    Draw,
+}
+
+/// Timings of the component `draw` and `update` calls, shared between the [`App`] that records
+/// them and the [`Profiler`] that shows them.
+///
+/// [`App`]: crate::app::App
+#[derive(Clone)]
+pub struct FrameTimings {
+    inner: Arc<Mutex<Timings>>,
+}
+
+struct Timings {
+    start: Instant,
+    budget: Duration,
+    /// Time spent in updates since the last frame.
+    pending: Duration,
+    frames: VecDeque<Duration>,
+    components: BTreeMap<&'static str, ComponentTimings>,
+    spans: VecDeque<TraceSpan>,
+}
+
+#[derive(Default)]
+struct ComponentTimings {
+    update: VecDeque<Duration>,
+    draw: VecDeque<Duration>,
+}
+
+struct TraceSpan {
+    name: &'static str,
+    phase: Phase,
+    start: Instant,
+    duration: Duration,
+}
+
+/// The 50th and 95th percentile and the maximum of a set of samples.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+impl Percentiles {
+    fn from_samples<'a>(samples: impl IntoIterator<Item = &'a Duration>) -> Self {
+        let mut samples: Vec<Duration> = samples.into_iter().copied().collect();
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort();
+        let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+        Self {
+            p50: percentile(50),
+            p95: percentile(95),
+            max: percentile(100),
+        }
+    }
+}
+
+fn push_sample<T>(samples: &mut VecDeque<T>, sample: T, capacity: usize) {
+    if samples.len() == capacity {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+impl FrameTimings {
+    /// Creates timings for a target frame rate, which determines the frame budget.
+    pub fn new(frame_rate: f64) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Timings {
+                start: Instant::now(),
+                budget: Duration::from_secs_f64(1.0 / frame_rate),
+                pending: Duration::ZERO,
+                frames: VecDeque::with_capacity(SAMPLES),
+                components: BTreeMap::new(),
+                spans: VecDeque::with_capacity(TRACE_SPANS),
+            })),
+        }
+    }
+
+    /// Records that the component `name` spent the time since `start` in `phase`.
+    pub fn record(&self, name: &'static str, phase: Phase, start: Instant) {
+        let duration = start.elapsed();
+        let Ok(mut timings) = self.inner.lock() else {
+            return;
+        };
+        let component = timings.components.entry(name).or_default();
+        match phase {
+            Phase::Update => push_sample(&mut component.update, duration, SAMPLES),
+            Phase::Draw => push_sample(&mut component.draw, duration, SAMPLES),
+        }
+        if phase == Phase::Update {
+            timings.pending += duration;
+        }
+        let span = TraceSpan {
+            name,
+            phase,
+            start,
+            duration,
+        };
+        push_sample(&mut timings.spans, span, TRACE_SPANS);
+    }
+
+    /// Records the end of a frame that started rendering at `start`.
+    ///
+    /// The frame time includes the updates handled since the previous frame, so that slow updates
+    /// show up as slow frames.
+    pub fn end_frame(&self, start: Instant) {
+        let duration = start.elapsed();
+        if let Ok(mut timings) = self.inner.lock() {
+            let frame_time = std::mem::take(&mut timings.pending) + duration;
+            push_sample(&mut timings.frames, frame_time, SAMPLES);
+        }
+    }
+
+    /// Writes the recorded spans to the data directory in the Chrome trace event format, which
+    /// can be loaded in Perfetto or `chrome://tracing`.
+    pub fn export_trace(&self) -> Result<PathBuf> {
+        let timings = self
+            .inner
+            .lock()
+            .map_err(|_| eyre!("frame timings are poisoned"))?;
+        let pid = std::process::id();
+        let events: Vec<_> = timings
+            .spans
+            .iter()
+            .map(|span| {
+                json!({
+                    "name": span.name,
+                    "cat": match span.phase {
+                        Phase::Update => "update",
+                        Phase::Draw => "draw",
+                    },
+                    "ph": "X",
+                    "ts": (span.start - timings.start).as_micros() as u64,
+                    "dur": span.duration.as_micros() as u64,
+                    "pid": pid,
+                    "tid": 1,
+                })
+            })
+            .collect();
+        let trace = json!({ "traceEvents": events, "displayTimeUnit": "ms" });
+        let directory = config::get_data_dir();
+        fs::create_dir_all(&directory)?;
+        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let path = directory.join(format!("trace-{timestamp}.json"));
+        fs::write(&path, serde_json::to_string(&trace)?)?;
+        Ok(path)
+    }
+}
+
+/// Shows the tick rate and frame rate, and when toggled, an overlay with frame time statistics
+/// for each component.
+pub struct Profiler {
+    timings: FrameTimings,
+    visible: bool,
+
+    last_tick_update: Instant,
+    tick_count: u32,
+    ticks_per_second: f64,
+
+    last_frame_update: Instant,
+    frame_count: u32,
+    frames_per_second: f64,
+}
+
+impl Profiler {
+    pub fn new(timings: FrameTimings) -> Self {
+        Self {
+            timings,
+            visible: false,
+            last_tick_update: Instant::now(),
+            tick_count: 0,
+            ticks_per_second: 0.0,
+            last_frame_update: Instant::now(),
+            frame_count: 0,
+            frames_per_second: 0.0,
+        }
+    }
+
+    fn app_tick(&mut self) -> Result<()> {
+        self.tick_count += 1;
+        let now = Instant::now();
+        let elapsed = (now - self.last_tick_update).as_secs_f64();
+        if elapsed >= 1.0 {
+            self.ticks_per_second = self.tick_count as f64 / elapsed;
+            self.last_tick_update = now;
+            self.tick_count = 0;
+        }
+        Ok(())
+    }
+
+    fn render_tick(&mut self) -> Result<()> {
+        self.frame_count += 1;
+        let now = Instant::now();
+        let elapsed = (now - self.last_frame_update).as_secs_f64();
+        if elapsed >= 1.0 {
+            self.frames_per_second = self.frame_count as f64 / elapsed;
+            self.last_frame_update = now;
+            self.frame_count = 0;
+        }
+        Ok(())
+    }
+
+    fn export_trace(&self) -> Action {
+        match self.timings.export_trace() {
+            Ok(path) => Action::Notify(
+                Severity::Success,
+                format!("Trace written to {}", path.display()),
+            ),
+            Err(err) => Action::Error(format!("Failed to write trace: {err}")),
+        }
+    }
+
+    fn draw_summary(&self, frame: &mut Frame, area: Rect, timings: &Timings) {
+        let [top, _] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+        let frame_times = Percentiles::from_samples(&timings.frames);
+        let message = format!(
+            "{:.2} ticks/sec, {:.2} FPS, p95 {}",
+            self.ticks_per_second,
+            self.frames_per_second,
+            format_duration(frame_times.p95)
+        );
+        let span = Span::styled(message, Style::new().dim());
+        let paragraph = Paragraph::new(span).right_aligned();
+        frame.render_widget(paragraph, top);
+    }
+
+    fn draw_overlay(&self, frame: &mut Frame, area: Rect, timings: &Timings) {
+        let area = area.inner(Margin::new(4, 2));
+        frame.render_widget(Clear, area);
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .title(" Profiler ")
+            .title_bottom(Line::from(" ctrl-t export trace  ctrl-p close ").centered());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [header, sparkline, histogram, table] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(5),
+            Constraint::Length(6),
+            Constraint::Min(3),
+        ])
+        .spacing(1)
+        .areas(inner);
+
+        let frame_times = Percentiles::from_samples(&timings.frames);
+        let over_budget = timings
+            .frames
+            .iter()
+            .filter(|time| **time > timings.budget)
+            .count();
+        let header_line = Line::from(vec![
+            Span::raw(format!(
+                "{:.2} FPS  frame p50 {}  p95 {}  max {}  budget {}  ",
+                self.frames_per_second,
+                format_duration(frame_times.p50),
+                format_duration(frame_times.p95),
+                format_duration(frame_times.max),
+                format_duration(timings.budget),
+            )),
+            Span::styled(
+                format!("{over_budget}/{} over budget", timings.frames.len()),
+                if over_budget > 0 {
+                    Style::new().red().bold()
+                } else {
+                    Style::new().green()
+                },
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(header_line), header);
+
+        // show the most recent frames that fit, with the frames over budget in red
+        let bars: Vec<SparklineBar> = timings
+            .frames
+            .iter()
+            .skip(
+                timings
+                    .frames
+                    .len()
+                    .saturating_sub(sparkline.width as usize),
+            )
+            .map(|time| {
+                let style = if *time > timings.budget {
+                    Style::new().red()
+                } else {
+                    Style::new().green()
+                };
+                SparklineBar::from(time.as_micros() as u64).style(style)
+            })
+            .collect();
+        let sparkline_widget = Sparkline::default()
+            .block(Block::new().title("Frame times".bold()))
+            .data(bars);
+        frame.render_widget(sparkline_widget, sparkline);
+
+        let mut counts = [0; HISTOGRAM_BUCKETS.len() + 1];
+        for time in &timings.frames {
+            let millis = time.as_millis() as u64;
+            let bucket = HISTOGRAM_BUCKETS
+                .iter()
+                .position(|bound| millis < *bound)
+                .unwrap_or(HISTOGRAM_BUCKETS.len());
+            counts[bucket] += 1;
+        }
+        let bars: Vec<Bar> = counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let label = match HISTOGRAM_BUCKETS.get(i) {
+                    Some(bound) => format!("<{bound}ms"),
+                    None => format!("≥{}ms", HISTOGRAM_BUCKETS[i - 1]),
+                };
+                Bar::default().value(*count).label(Line::from(label))
+            })
+            .collect();
+        let histogram_widget = BarChart::default()
+            .block(Block::new().title("Histogram".bold()))
+            .bar_width(7)
+            .bar_gap(1)
+            .data(BarGroup::default().bars(&bars));
+        frame.render_widget(histogram_widget, histogram);
+
+        let rows = timings.components.iter().map(|(name, component)| {
+            let draw = Percentiles::from_samples(&component.draw);
+            let update = Percentiles::from_samples(&component.update);
+            let mut cells = vec![name.to_string()];
+            for stats in [draw, update] {
+                cells.extend([stats.p50, stats.p95, stats.max].map(format_duration));
+            }
+            Row::new(cells)
+        });
+        let widths = [Constraint::Fill(1)]
+            .into_iter()
+            .chain([Constraint::Length(9); 6]);
+        let header_row = Row::new([
+            "Component",
+            "draw p50",
+            "p95",
+            "max",
+            "update p50",
+            "p95",
+            "max",
+        ])
+        .bold();
+        frame.render_widget(Table::new(rows, widths).header(header_row), table);
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+impl Component for Profiler {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Tick => self.app_tick()?,
+            Action::Render => self.render_tick()?,
+            Action::ToggleProfiler => self.visible = !self.visible,
+            Action::ExportTrace => return Ok(Some(self.export_trace())),
+            _ => {}
+        };
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let timings = self.timings.clone();
+        let timings = timings
+            .inner
+            .lock()
+            .map_err(|_| eyre!("frame timings are poisoned"))?;
+        if self.visible {
+            self.draw_overlay(frame, area, &timings);
+        } else {
+            self.draw_summary(frame, area, &timings);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let samples: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(
+            Percentiles::from_samples(&samples),
+            Percentiles {
+                p50: Duration::from_millis(50),
+                p95: Duration::from_millis(95),
+                max: Duration::from_millis(100),
+            }
+        );
+        assert_eq!(Percentiles::from_samples(&[]), Percentiles::default());
+    }
+
+    #[test]
+    fn test_frame_time_includes_updates() {
+        let timings = FrameTimings::new(60.0);
+        let start = Instant::now() - Duration::from_millis(20);
+        timings.record("Home", Phase::Update, start);
+        timings.end_frame(Instant::now());
+        let inner = timings.inner.lock().unwrap();
+        assert!(inner.frames[0] >= Duration::from_millis(20));
+        assert_eq!(inner.components["Home"].update.len(), 1);
+        assert_eq!(inner.spans.len(), 1);
+    }
+}
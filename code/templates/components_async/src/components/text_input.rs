@@ -0,0 +1,500 @@
+#![allow(dead_code)] // Remove this once you start using the code
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Position, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use super::Component;
+use crate::action::Action;
+
+type Validator = Box<dyn Fn(&str) -> Result<(), String>>;
+type Submit = Box<dyn Fn(String) -> Action>;
+
+/// A single line text input.
+///
+/// The cursor moves by grapheme and by word and understands the usual readline/emacs shortcuts:
+///
+/// - `←`/`→`, `ctrl-b`/`ctrl-f`: previous/next character, `alt-b`/`alt-f`, `ctrl-←`/`ctrl-→`:
+///   previous/next word, `home`/`end`, `ctrl-a`/`ctrl-e`: start/end of the line. Holding shift
+///   while moving extends the selection.
+/// - `backspace`/`ctrl-h` and `delete`/`ctrl-d`: delete a character or the selection,
+///   `ctrl-w`/`alt-backspace` and `alt-d`: delete a word, `ctrl-u` and `ctrl-k`: delete to the
+///   start/end of the line. Deleted words and lines can be pasted back with `ctrl-y`.
+/// - `alt-w`: copy the selection to the clipboard, `enter`: submit.
+///
+/// Like other components the input receives every key event, so it only reacts while it is
+/// focused.
+pub struct TextInput {
+    value: String,
+    /// Byte offset of the cursor, always on a grapheme boundary.
+    cursor: usize,
+    /// Byte offset of the other end of the selection.
+    anchor: Option<usize>,
+    /// The number of columns scrolled out of view on the left.
+    scroll: usize,
+    focused: bool,
+    placeholder: String,
+    /// The maximum length, in graphemes.
+    max_length: Option<usize>,
+    validator: Option<Validator>,
+    on_submit: Option<Submit>,
+    error: Option<String>,
+    /// The text removed by the last kill command, inserted again by `ctrl-y`.
+    kill_buffer: String,
+    style: Style,
+}
+
+impl Default for TextInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self {
+            value: String::new(),
+            cursor: 0,
+            anchor: None,
+            scroll: 0,
+            focused: false,
+            placeholder: String::new(),
+            max_length: None,
+            validator: None,
+            on_submit: None,
+            error: None,
+            kill_buffer: String::new(),
+            style: Style::new(),
+        }
+    }
+
+    /// Sets the initial value and moves the cursor to its end.
+    pub fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.set_value(value);
+        self
+    }
+
+    /// Sets the text shown while the input is empty.
+    pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    /// Limits the value to `max_length` graphemes. Longer input is truncated.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Sets a callback that checks the value after every change. The value can't be submitted
+    /// while the callback returns an error, which is available from [`TextInput::error`].
+    pub fn validator(mut self, validator: impl Fn(&str) -> Result<(), String> + 'static) -> Self {
+        self.validator = Some(Box::new(validator));
+        self.validate();
+        self
+    }
+
+    /// Sets a callback that turns the submitted value into an action.
+    pub fn on_submit(mut self, on_submit: impl Fn(String) -> Action + 'static) -> Self {
+        self.on_submit = Some(Box::new(on_submit));
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+        if let Some(max_length) = self.max_length {
+            self.value = self.value.graphemes(true).take(max_length).collect();
+        }
+        self.cursor = self.value.len();
+        self.anchor = None;
+        self.validate();
+    }
+
+    /// The error returned by the validator for the current value.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// The selected text, if any.
+    pub fn selection(&self) -> Option<&str> {
+        self.selection_range()
+            .map(|(start, end)| &self.value[start..end])
+    }
+
+    fn selection_range(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor?;
+        (anchor != self.cursor).then(|| (anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    fn validate(&mut self) {
+        self.error = self
+            .validator
+            .as_ref()
+            .and_then(|validator| validator(&self.value).err());
+    }
+
+    fn prev_grapheme(&self, from: usize) -> usize {
+        self.value[..from]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(index, _)| index)
+    }
+
+    fn next_grapheme(&self, from: usize) -> usize {
+        self.value[from..]
+            .graphemes(true)
+            .next()
+            .map_or(from, |grapheme| from + grapheme.len())
+    }
+
+    /// The start of the word before `from`, skipping any whitespace and punctuation first.
+    fn prev_word(&self, from: usize) -> usize {
+        self.value[..from]
+            .split_word_bound_indices()
+            .rev()
+            .find(|(_, word)| is_word(word))
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// The end of the word after `from`, skipping any whitespace and punctuation first.
+    fn next_word(&self, from: usize) -> usize {
+        self.value[from..]
+            .split_word_bound_indices()
+            .find(|(_, word)| is_word(word))
+            .map_or(self.value.len(), |(index, word)| from + index + word.len())
+    }
+
+    fn move_to(&mut self, position: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = position;
+    }
+
+    /// Removes the text between the cursor and `position`, returning it.
+    fn delete_to(&mut self, position: usize) -> String {
+        let (start, end) = (self.cursor.min(position), self.cursor.max(position));
+        self.cursor = start;
+        self.anchor = None;
+        let removed = self.value.drain(start..end).collect();
+        self.validate();
+        removed
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection_range() {
+            Some((start, end)) => {
+                self.cursor = end;
+                self.delete_to(start);
+                true
+            }
+            None => {
+                self.anchor = None;
+                false
+            }
+        }
+    }
+
+    /// Deletes the selection, or the grapheme before the cursor when nothing is selected.
+    fn delete_backward(&mut self) {
+        if !self.delete_selection() {
+            self.delete_to(self.prev_grapheme(self.cursor));
+        }
+    }
+
+    /// Deletes the selection, or the grapheme after the cursor when nothing is selected.
+    fn delete_forward(&mut self) {
+        if !self.delete_selection() {
+            self.delete_to(self.next_grapheme(self.cursor));
+        }
+    }
+
+    fn kill_to(&mut self, position: usize) {
+        let removed = self.delete_to(position);
+        if !removed.is_empty() {
+            self.kill_buffer = removed;
+        }
+    }
+
+    /// Inserts `text` at the cursor, replacing the selection. Control characters are dropped and
+    /// the text is truncated to the maximum length.
+    pub fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        let available = self.max_length.map_or(usize::MAX, |max_length| {
+            max_length.saturating_sub(self.value.graphemes(true).count())
+        });
+        let text: String = text.graphemes(true).take(available).collect();
+        self.value.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+        self.validate();
+    }
+
+    fn submit(&mut self) -> Option<Action> {
+        if !self.is_valid() {
+            return None;
+        }
+        self.on_submit
+            .as_ref()
+            .map(|on_submit| on_submit(self.value.clone()))
+    }
+
+    /// Applies an editing key, returning the action it produced, if any.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Left if ctrl => self.move_to(self.prev_word(self.cursor), shift),
+            KeyCode::Right if ctrl => self.move_to(self.next_word(self.cursor), shift),
+            KeyCode::Left => self.move_to(self.prev_grapheme(self.cursor), shift),
+            KeyCode::Right => self.move_to(self.next_grapheme(self.cursor), shift),
+            KeyCode::Home => self.move_to(0, shift),
+            KeyCode::End => self.move_to(self.value.len(), shift),
+            KeyCode::Backspace if alt => self.kill_to(self.prev_word(self.cursor)),
+            KeyCode::Backspace => self.delete_backward(),
+            KeyCode::Delete => self.delete_forward(),
+            KeyCode::Enter => return self.submit(),
+            KeyCode::Char(c) if ctrl => match c {
+                'a' => self.move_to(0, false),
+                'e' => self.move_to(self.value.len(), false),
+                'b' => self.move_to(self.prev_grapheme(self.cursor), false),
+                'f' => self.move_to(self.next_grapheme(self.cursor), false),
+                'h' => self.delete_backward(),
+                'd' => self.delete_forward(),
+                'w' => self.kill_to(self.prev_word(self.cursor)),
+                'u' => self.kill_to(0),
+                'k' => self.kill_to(self.value.len()),
+                'y' => self.insert(&self.kill_buffer.clone()),
+                _ => {}
+            },
+            KeyCode::Char(c) if alt => match c {
+                'b' => self.move_to(self.prev_word(self.cursor), false),
+                'f' => self.move_to(self.next_word(self.cursor), false),
+                'd' => self.kill_to(self.next_word(self.cursor)),
+                'w' => return self.selection().map(|text| Action::Copy(text.to_string())),
+                _ => {}
+            },
+            KeyCode::Char(c) => self.insert(c.encode_utf8(&mut [0; 4])),
+            _ => {}
+        }
+        None
+    }
+
+    /// The column of the cursor, counted in terminal cells from the start of the value.
+    fn cursor_column(&self) -> usize {
+        self.value[..self.cursor].width()
+    }
+
+    /// Scrolls horizontally so that the cursor stays within `width` columns.
+    fn scroll_to_cursor(&mut self, width: usize) {
+        let column = self.cursor_column();
+        if column < self.scroll {
+            self.scroll = column;
+        } else if column >= self.scroll + width {
+            // a wide grapheme cut in half by the edge isn't drawn, so scroll past all of it to keep
+            // the cursor next to the text
+            self.scroll = self.grapheme_boundary_from(column + 1 - width);
+        }
+    }
+
+    /// The first column at or after `column` where a grapheme starts or the value ends.
+    fn grapheme_boundary_from(&self, column: usize) -> usize {
+        let mut boundary = 0;
+        for grapheme in self.value.graphemes(true) {
+            if boundary >= column {
+                break;
+            }
+            boundary += grapheme.width();
+        }
+        boundary
+    }
+
+    /// The spans of the part of the value that is visible in `width` columns.
+    fn visible_spans(&self, width: usize) -> Vec<Span<'_>> {
+        let selection = self.selection_range();
+        let selected_style = self.style.reversed();
+        let mut spans: Vec<Span> = Vec::new();
+        let mut column = 0;
+        for (index, grapheme) in self.value.grapheme_indices(true) {
+            let start = column;
+            column += grapheme.width();
+            // graphemes that are cut off at either edge are left out
+            if start < self.scroll {
+                continue;
+            }
+            if column > self.scroll + width {
+                break;
+            }
+            let style = match selection {
+                Some((start, end)) if (start..end).contains(&index) => selected_style,
+                _ => self.style,
+            };
+            match spans.last_mut() {
+                Some(span) if span.style == style => span.content.to_mut().push_str(grapheme),
+                _ => spans.push(Span::styled(grapheme.to_string(), style)),
+            }
+        }
+        spans
+    }
+}
+
+fn is_word(segment: &str) -> bool {
+    segment.chars().any(char::is_alphanumeric)
+}
+
+impl Component for TextInput {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if !self.focused {
+            return Ok(None);
+        }
+        Ok(self.handle_key(key))
+    }
+
+    fn handle_paste_event(&mut self, text: String) -> Result<Option<Action>> {
+        if self.focused {
+            // the input is single line
+            self.insert(&text.replace(['\r', '\n'], " "));
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let width = area.width.saturating_sub(1) as usize; // keep 1 for the cursor at the end
+        self.scroll_to_cursor(width.max(1));
+        let line = if self.value.is_empty() {
+            Line::from(Span::styled(self.placeholder.as_str(), self.style.dim()))
+        } else {
+            Line::from(self.visible_spans(width.max(1)))
+        };
+        let style = if self.is_valid() {
+            Style::new()
+        } else {
+            Style::new().red()
+        };
+        frame.render_widget(Paragraph::new(line).style(style), area);
+        if self.focused {
+            let column = (self.cursor_column() - self.scroll) as u16;
+            frame.set_cursor_position(Position::new(area.x + column, area.y));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_word_movement_and_kill() {
+        let mut input = TextInput::new().with_value("hello, wide world");
+        input.handle_key(key(KeyCode::Char('b'), KeyModifiers::ALT));
+        assert_eq!(input.cursor, 12);
+        input.handle_key(key(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "hello, world");
+        input.handle_key(key(KeyCode::Char('e'), KeyModifiers::CONTROL));
+        input.handle_key(key(KeyCode::Char('y'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "hello, worldwide ");
+    }
+
+    #[test]
+    fn test_graphemes_and_wide_characters() {
+        let mut input = TextInput::new().with_value("ae\u{301}日本");
+        assert_eq!(input.cursor_column(), 6);
+        input.handle_key(key(KeyCode::Left, KeyModifiers::NONE));
+        input.handle_key(key(KeyCode::Left, KeyModifiers::NONE));
+        assert_eq!(input.cursor_column(), 2);
+        input.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE));
+        assert_eq!(input.value(), "a日本");
+    }
+
+    #[test]
+    fn test_scroll_past_wide_characters() {
+        let mut input = TextInput::new().with_value("日本語");
+        // a 5 cell field leaves 4 columns for the text, which would cut 本 in half
+        input.scroll_to_cursor(4);
+        assert_eq!(input.scroll, 4);
+        let text: String = input
+            .visible_spans(4)
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(text, "語");
+        assert_eq!(input.cursor_column() - input.scroll, 2);
+    }
+
+    #[test]
+    fn test_selection_and_max_length() {
+        let mut input = TextInput::new().with_value("abc").max_length(4);
+        input.handle_key(key(KeyCode::Left, KeyModifiers::SHIFT));
+        input.handle_key(key(KeyCode::Left, KeyModifiers::SHIFT));
+        assert_eq!(input.selection(), Some("bc"));
+        input.insert("xyz");
+        assert_eq!(input.value(), "axyz");
+    }
+
+    #[test]
+    fn test_validator_blocks_submit() {
+        let mut input = TextInput::new()
+            .validator(|value| {
+                value
+                    .parse::<u32>()
+                    .map(drop)
+                    .map_err(|err| err.to_string())
+            })
+            .on_submit(Action::Error);
+        input.insert("4x");
+        assert!(input.error().is_some());
+        assert_eq!(
+            input.handle_key(key(KeyCode::Enter, KeyModifiers::NONE)),
+            None
+        );
+        input.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE));
+        assert_eq!(
+            input.handle_key(key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(Action::Error("4".into()))
+        );
+    }
+}
@@ -1,33 +1,61 @@
 #![allow(dead_code)] // Remove this once you start using the code
 // ANCHOR: all
 
-use std::{collections::HashMap, env, path::PathBuf, sync::LazyLock};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    env, fmt,
+    path::{Path, PathBuf},
+    sync::{LazyLock, OnceLock},
+};
 
 use color_eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use derive_deref::{Deref, DerefMut};
 use directories::ProjectDirs;
 use ratatui::style::{Color, Modifier, Style};
-use serde::{Deserialize, de::Deserializer};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{
+    Deserialize, Serialize, Serializer,
+    de::{self, Deserializer},
+    ser::SerializeMap,
+};
+use strum::VariantNames;
 use tracing::error;
 
 use crate::{action::Action, app::Mode};
 
-const CONFIG: &str = include_str!("../.config/config.json5");
+/// The default configuration, embedded in the binary.
+pub const CONFIG: &str = include_str!("../.config/config.json5");
 
-#[derive(Clone, Debug, Deserialize, Default)]
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, Default)]
 pub struct AppConfig {
+    /// The directory for logs and other application data
     #[serde(default)]
     pub data_dir: PathBuf,
+    /// The directory containing the user config file
     #[serde(default)]
     pub config_dir: PathBuf,
 }
 
-#[derive(Clone, Debug, Default, Deserialize)]
+/// How the log files are rotated. Unset values use the defaults of the rotation policy.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct LogConfig {
+    /// Start a new log file once the current one is larger than this many bytes
+    pub max_size: Option<u64>,
+    /// Start a new log file once the current one is older than this many seconds
+    pub max_age: Option<u64>,
+    /// The number of log files to keep, including the current one
+    pub retention: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub struct Config {
     #[serde(default, flatten)]
     pub config: AppConfig,
     #[serde(default)]
+    pub log: LogConfig,
+    #[serde(default)]
     pub keybindings: KeyBindings,
     #[serde(default)]
     pub styles: Styles,
@@ -46,58 +74,242 @@ pub static CONFIG_FOLDER: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
         .map(PathBuf::from)
 });
 
+/// The `data_dir` of the loaded configuration, see [`set_data_dir`].
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// The name of the per-project config file, searched for from the current directory upwards.
+pub static PROJECT_CONFIG_FILE: LazyLock<String> =
+    LazyLock::new(|| format!(".{}.json5", env!("CARGO_PKG_NAME")));
+
+/// The file names that are searched for in the config directory, with their formats.
+pub const CONFIG_FILES: [(&str, config::FileFormat); 5] = [
+    ("config.json5", config::FileFormat::Json5),
+    ("config.json", config::FileFormat::Json),
+    ("config.yaml", config::FileFormat::Yaml),
+    ("config.toml", config::FileFormat::Toml),
+    ("config.ini", config::FileFormat::Ini),
+];
+
+/// Where a configuration value came from.
+///
+/// The configuration is merged from these sources in the order of the variants, so values from
+/// later sources take precedence over earlier ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// The defaults embedded in the binary.
+    Default,
+    /// A file in the system-wide config directory.
+    System(PathBuf),
+    /// A file in the user config directory.
+    User(PathBuf),
+    /// A `.{app}.json5` file in the current directory or one of its parents.
+    Project(PathBuf),
+    /// A `{PROJECT}__KEY` environment variable.
+    Environment,
+    /// A `--set key=value` command line argument.
+    CommandLine,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::System(path) => write!(f, "system ({})", path.display()),
+            ConfigSource::User(path) => write!(f, "user ({})", path.display()),
+            ConfigSource::Project(path) => write!(f, "project ({})", path.display()),
+            ConfigSource::Environment => write!(f, "environment"),
+            ConfigSource::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// The effective configuration values, flattened to dotted keys, with the source of each value.
+///
+/// The merged [`Config`] is built by config-rs, which lowercases keys, so the values are keyed on
+/// the lowercased key: a `<ctrl-d>` in one source overrides a `<Ctrl-d>` in another. Each entry
+/// also keeps the key as it is spelled in its source, so that `config dump` shows keys such as
+/// `<Ctrl-d>` the way the user wrote them.
+pub type Provenance = BTreeMap<String, ProvenanceEntry>;
+
+/// An effective configuration value and where it came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProvenanceEntry {
+    /// The dotted key, spelled as in the source.
+    pub key: String,
+    pub value: serde_json::Value,
+    pub source: ConfigSource,
+}
+
 impl Config {
     pub fn new() -> Result<Self, config::ConfigError> {
-        let default_config: Config = json5::from_str(CONFIG).unwrap();
-        let data_dir = get_data_dir();
-        let config_dir = get_config_dir();
-        let mut builder = config::Config::builder()
-            .set_default("data_dir", data_dir.to_str().unwrap())?
-            .set_default("config_dir", config_dir.to_str().unwrap())?;
-
-        let config_files = [
-            ("config.json5", config::FileFormat::Json5),
-            ("config.json", config::FileFormat::Json),
-            ("config.yaml", config::FileFormat::Yaml),
-            ("config.toml", config::FileFormat::Toml),
-            ("config.ini", config::FileFormat::Ini),
-        ];
-        let mut found_config = false;
-        for (file, format) in &config_files {
-            let source = config::File::from(config_dir.join(file))
-                .format(*format)
-                .required(false);
-            builder = builder.add_source(source);
-            if config_dir.join(file).exists() {
-                found_config = true
-            }
+        Self::with_overrides(&[])
+    }
+
+    /// Loads the configuration, with `overrides` taking precedence over every other source.
+    pub fn with_overrides(overrides: &[(String, String)]) -> Result<Self, config::ConfigError> {
+        Self::load(overrides).map(|(config, _)| config)
+    }
+
+    /// Loads the configuration and records which source each effective value came from.
+    pub fn load(overrides: &[(String, String)]) -> Result<(Self, Provenance), config::ConfigError> {
+        let mut builder = config::Config::builder();
+        let mut provenance = Provenance::new();
+        for (source, layer, values) in config_layers(overrides)? {
+            flatten_values("", values, &source, &mut provenance);
+            builder = builder.add_source(layer);
         }
-        if !found_config {
-            error!("No configuration file found. Application may not behave as expected");
+        let cfg = builder.build()?.try_deserialize()?;
+        Ok((cfg, provenance))
+    }
+
+    /// Returns a JSON Schema describing the config file format.
+    pub fn json_schema() -> serde_json::Value {
+        let schema = schemars::schema_for!(Config);
+        serde_json::to_value(schema).expect("schemas are always valid JSON")
+    }
+}
+
+/// A configuration source, merged into the [`Config`], with its values as they were written.
+type Layer = (ConfigSource, config::Config, serde_json::Value);
+
+/// Returns each configuration source, from lowest to highest precedence.
+fn config_layers(overrides: &[(String, String)]) -> Result<Vec<Layer>, config::ConfigError> {
+    let data_dir = default_data_dir();
+    let config_dir = get_config_dir();
+    let default_file = config::File::from_str(CONFIG, config::FileFormat::Json5);
+    let mut default_values = raw_values(&default_file)?;
+    if let serde_json::Value::Object(map) = &mut default_values {
+        map.insert("data_dir".into(), data_dir.to_string_lossy().into());
+        map.insert("config_dir".into(), config_dir.to_string_lossy().into());
+    }
+    let defaults = config::Config::builder()
+        .set_default("data_dir", data_dir.to_str().unwrap())?
+        .set_default("config_dir", config_dir.to_str().unwrap())?
+        .add_source(default_file)
+        .build()?;
+    let mut layers = vec![(ConfigSource::Default, defaults, default_values)];
+
+    if let Some(system_dir) = get_system_config_dir() {
+        for (path, format) in existing_config_files(&system_dir) {
+            layers.push(file_layer(
+                ConfigSource::System(path.clone()),
+                path,
+                format,
+            )?);
         }
+    }
+
+    let user_files = existing_config_files(&config_dir);
+    if user_files.is_empty() {
+        error!("No configuration file found. Application may not behave as expected");
+    }
+    for (path, format) in user_files {
+        layers.push(file_layer(ConfigSource::User(path.clone()), path, format)?);
+    }
+
+    if let Some(path) = find_project_config() {
+        let source = ConfigSource::Project(path.clone());
+        layers.push(file_layer(source, path, config::FileFormat::Json5)?);
+    }
 
-        let mut cfg: Self = builder.build()?.try_deserialize()?;
+    let environment = config::Environment::with_prefix(&PROJECT_NAME)
+        .prefix_separator("__")
+        .separator("__");
+    // environment variable names are lowercased by config-rs, there is no other spelling to show
+    let environment_values = raw_values(&environment)?;
+    let environment = config::Config::builder().add_source(environment).build()?;
+    layers.push((ConfigSource::Environment, environment, environment_values));
+
+    let mut command_line = config::Config::builder();
+    let mut command_line_values = serde_json::Map::new();
+    for (key, value) in overrides {
+        command_line = command_line.set_override(key, value.as_str())?;
+        command_line_values.insert(key.clone(), value.as_str().into());
+    }
+    layers.push((
+        ConfigSource::CommandLine,
+        command_line.build()?,
+        command_line_values.into(),
+    ));
+    Ok(layers)
+}
+
+fn file_layer(
+    source: ConfigSource,
+    path: PathBuf,
+    format: config::FileFormat,
+) -> Result<Layer, config::ConfigError> {
+    let file = config::File::from(path).format(format);
+    let values = raw_values(&file)?;
+    let layer = config::Config::builder().add_source(file).build()?;
+    Ok((source, layer, values))
+}
+
+/// The values of a source with its keys as they were written, before config-rs lowercases them.
+fn raw_values(source: &impl config::Source) -> Result<serde_json::Value, config::ConfigError> {
+    config::Value::new(None, source.collect()?).try_deserialize()
+}
+
+/// Returns the config files from [`CONFIG_FILES`] that exist in `directory`.
+pub fn existing_config_files(directory: &Path) -> Vec<(PathBuf, config::FileFormat)> {
+    CONFIG_FILES
+        .iter()
+        .map(|(file, format)| (directory.join(file), *format))
+        .filter(|(path, _)| path.exists())
+        .collect()
+}
 
-        for (mode, default_bindings) in default_config.keybindings.iter() {
-            let user_bindings = cfg.keybindings.entry(*mode).or_default();
-            for (key, cmd) in default_bindings.iter() {
-                user_bindings
-                    .entry(key.clone())
-                    .or_insert_with(|| cmd.clone());
+/// Finds the nearest [`PROJECT_CONFIG_FILE`] in the current directory or one of its parents.
+pub fn find_project_config() -> Option<PathBuf> {
+    let current_dir = env::current_dir().ok()?;
+    current_dir
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE.as_str()))
+        .find(|path| path.is_file())
+}
+
+fn flatten_values(
+    prefix: &str,
+    value: serde_json::Value,
+    source: &ConfigSource,
+    provenance: &mut Provenance,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten_values(&key, value, source, provenance);
             }
         }
-        for (mode, default_styles) in default_config.styles.iter() {
-            let user_styles = cfg.styles.entry(*mode).or_default();
-            for (style_key, style) in default_styles.iter() {
-                user_styles.entry(style_key.clone()).or_insert(*style);
-            }
+        value => {
+            let entry = ProvenanceEntry {
+                key: prefix.to_string(),
+                value,
+                source: source.clone(),
+            };
+            provenance.insert(prefix.to_lowercase(), entry);
         }
-
-        Ok(cfg)
     }
 }
 
+/// The directory for logs, sessions and crash reports: the `data_dir` of the loaded configuration
+/// once [`set_data_dir`] has been called, and [`default_data_dir`] before that.
 pub fn get_data_dir() -> PathBuf {
+    DATA_DIR.get().cloned().unwrap_or_else(default_data_dir)
+}
+
+/// Makes [`get_data_dir`] return the `data_dir` of the loaded configuration. Only the first call
+/// has an effect.
+pub fn set_data_dir(directory: PathBuf) {
+    let _ = DATA_DIR.set(directory);
+}
+
+/// The `{PROJECT}_DATA` environment variable, or else the platform's data directory.
+pub fn default_data_dir() -> PathBuf {
     let directory = if let Some(s) = DATA_FOLDER.clone() {
         s
     } else if let Some(proj_dirs) = project_directory() {
@@ -119,6 +331,18 @@ pub fn get_config_dir() -> PathBuf {
     directory
 }
 
+/// The directory for configuration shared by all users of the machine.
+pub fn get_system_config_dir() -> Option<PathBuf> {
+    let directory = if cfg!(windows) {
+        PathBuf::from(env::var_os("PROGRAMDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support")
+    } else {
+        PathBuf::from("/etc")
+    };
+    Some(directory.join(env!("CARGO_PKG_NAME")))
+}
+
 fn project_directory() -> Option<ProjectDirs> {
     ProjectDirs::from("com", "kdheepak", env!("CARGO_PKG_NAME"))
 }
@@ -138,16 +362,117 @@ impl<'de> Deserialize<'de> for KeyBindings {
             .map(|(mode, inner_map)| {
                 let converted_inner_map = inner_map
                     .into_iter()
-                    .map(|(key_str, cmd)| (parse_key_sequence(&key_str).unwrap(), cmd))
-                    .collect();
-                (mode, converted_inner_map)
+                    .map(|(key_str, cmd)| Ok((parse_key_sequence(&key_str)?, cmd)))
+                    .collect::<Result<_, String>>()?;
+                Ok((mode, converted_inner_map))
             })
-            .collect();
+            .collect::<Result<_, String>>()
+            .map_err(de::Error::custom)?;
 
         Ok(KeyBindings(keybindings))
     }
 }
 
+impl Serialize for KeyBindings {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (mode, bindings) in self.iter() {
+            let bindings: HashMap<String, &Action> = bindings
+                .iter()
+                .map(|(keys, action)| (key_sequence_to_string(keys), action))
+                .collect();
+            map.serialize_entry(mode, &bindings)?;
+        }
+        map.end()
+    }
+}
+
+impl JsonSchema for KeyBindings {
+    fn schema_name() -> Cow<'static, str> {
+        "KeyBindings".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let action = generator.subschema_for::<Action>();
+        json_schema!({
+            "description": "The actions to send for each key sequence, grouped by mode",
+            "type": "object",
+            "propertyNames": { "enum": Mode::VARIANTS },
+            "additionalProperties": {
+                "type": "object",
+                "propertyNames": { "pattern": key_sequence_pattern() },
+                "additionalProperties": action,
+            },
+        })
+    }
+}
+
+/// Key names understood by `parse_key_code_with_modifiers`, in addition to single characters.
+const KEY_NAMES: [&str; 30] = [
+    "esc",
+    "enter",
+    "left",
+    "right",
+    "up",
+    "down",
+    "home",
+    "end",
+    "pageup",
+    "pagedown",
+    "backtab",
+    "backspace",
+    "delete",
+    "insert",
+    "f1",
+    "f2",
+    "f3",
+    "f4",
+    "f5",
+    "f6",
+    "f7",
+    "f8",
+    "f9",
+    "f10",
+    "f11",
+    "f12",
+    "space",
+    "hyphen",
+    "minus",
+    "tab",
+];
+
+/// A regular expression matching the strings accepted by [`parse_key_sequence`], e.g. `<q>` or
+/// `<Ctrl-a><Ctrl-b>`.
+///
+/// JSON Schema patterns don't support case-insensitive matching, so every letter is matched as a
+/// character class like `[cC]`.
+fn key_sequence_pattern() -> String {
+    let any_case = |word: &str| -> String {
+        word.chars()
+            .map(|c| match c {
+                c if c.is_ascii_alphabetic() => {
+                    format!("[{}{}]", c.to_ascii_lowercase(), c.to_ascii_uppercase())
+                }
+                c => c.to_string(),
+            })
+            .collect()
+    };
+    let modifiers = ["ctrl", "alt", "shift"].map(any_case).join("|");
+    let mut names: Vec<&str> = KEY_NAMES.to_vec();
+    // match the longest names first so that e.g. `f10` isn't matched as `f1`
+    names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+    let names = names
+        .into_iter()
+        .map(any_case)
+        .collect::<Vec<_>>()
+        .join("|");
+    let key = format!("(({modifiers})-)*({names}|[^<>])");
+    format!("^(<{key}>)+$|^{key}$")
+}
+
 fn parse_key_event(raw: &str) -> Result<KeyEvent, String> {
     let raw_lower = raw.to_ascii_lowercase();
     let (remaining, modifiers) = extract_modifiers(&raw_lower);
@@ -247,7 +572,7 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
         KeyCode::Delete => "delete",
         KeyCode::Insert => "insert",
         KeyCode::F(c) => {
-            char = format!("f({c})");
+            char = format!("f{c}");
             &char
         }
         KeyCode::Char(' ') => "space",
@@ -292,6 +617,12 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
     key
 }
 
+pub fn key_sequence_to_string(keys: &[KeyEvent]) -> String {
+    keys.iter()
+        .map(|key| format!("<{}>", key_event_to_string(key)))
+        .collect()
+}
+
 pub fn parse_key_sequence(raw: &str) -> Result<Vec<KeyEvent>, String> {
     if raw.chars().filter(|c| *c == '>').count() != raw.chars().filter(|c| *c == '<').count() {
         return Err(format!("Unable to parse `{}`", raw));
@@ -344,6 +675,47 @@ impl<'de> Deserialize<'de> for Styles {
     }
 }
 
+impl Serialize for Styles {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (mode, styles) in self.iter() {
+            let styles: HashMap<&String, String> = styles
+                .iter()
+                .map(|(name, style)| (name, style_to_string(style)))
+                .collect();
+            map.serialize_entry(mode, &styles)?;
+        }
+        map.end()
+    }
+}
+
+impl JsonSchema for Styles {
+    fn schema_name() -> Cow<'static, str> {
+        "Styles".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        let color = "(black|red|green|yellow|blue|magenta|cyan|white|color[0-9]{1,3}|gr[ae]y[0-9]{1,2}|rgb[0-5]{3})";
+        let modifiers = "((bold|underline|inverse|bright)( |$))*";
+        json_schema!({
+            "description": "Named styles, grouped by mode",
+            "type": "object",
+            "propertyNames": { "enum": Mode::VARIANTS },
+            "additionalProperties": {
+                "type": "object",
+                "additionalProperties": {
+                    "description": "A style such as `bold red on blue`",
+                    "type": "string",
+                    "pattern": format!("^{modifiers}{color}?( ?on {modifiers}{color})?$"),
+                },
+            },
+        })
+    }
+}
+
 pub fn parse_style(line: &str) -> Style {
     let (foreground, background) =
         line.split_at(line.to_lowercase().find("on ").unwrap_or(line.len()));
@@ -361,6 +733,43 @@ pub fn parse_style(line: &str) -> Style {
     style
 }
 
+/// The inverse of [`parse_style`], producing a string that parses back to the same style.
+pub fn style_to_string(style: &Style) -> String {
+    let mut parts = Vec::new();
+    if style.add_modifier.contains(Modifier::BOLD) {
+        parts.push("bold".to_string());
+    }
+    if style.add_modifier.contains(Modifier::UNDERLINED) {
+        parts.push("underline".to_string());
+    }
+    if style.add_modifier.contains(Modifier::REVERSED) {
+        parts.push("inverse".to_string());
+    }
+    if let Some(fg) = style.fg.and_then(color_to_string) {
+        parts.push(fg);
+    }
+    if let Some(bg) = style.bg.and_then(color_to_string) {
+        parts.push(format!("on {bg}"));
+    }
+    parts.join(" ")
+}
+
+fn color_to_string(color: Color) -> Option<String> {
+    let name = match color {
+        Color::Indexed(0) => "black",
+        Color::Indexed(1) => "red",
+        Color::Indexed(2) => "green",
+        Color::Indexed(3) => "yellow",
+        Color::Indexed(4) => "blue",
+        Color::Indexed(5) => "magenta",
+        Color::Indexed(6) => "cyan",
+        Color::Indexed(7) => "white",
+        Color::Indexed(c) => return Some(format!("color{c}")),
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
 fn process_color_string(color_str: &str) -> (String, Modifier) {
     let color = color_str
         .replace("grey", "gray")
@@ -516,6 +925,54 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_command_line_overrides() -> Result<()> {
+        let overrides = [
+            ("data_dir".to_string(), "/tmp/overridden".to_string()),
+            ("log.retention".to_string(), "3".to_string()),
+        ];
+        let (c, provenance) = Config::load(&overrides)?;
+        assert_eq!(c.config.data_dir, PathBuf::from("/tmp/overridden"));
+        assert_eq!(c.log.retention, Some(3));
+        let data_dir = &provenance["data_dir"];
+        assert_eq!(data_dir.value, "/tmp/overridden");
+        assert_eq!(data_dir.source, ConfigSource::CommandLine);
+        let quit = &provenance["keybindings.home.<ctrl-d>"];
+        assert_eq!(quit.key, "keybindings.Home.<Ctrl-d>");
+        assert_eq!(quit.value, "Quit");
+        assert_eq!(quit.source, ConfigSource::Default);
+        Ok(())
+    }
+
+    #[test]
+    fn test_provenance_merges_key_spellings() {
+        let mut provenance = Provenance::new();
+        let defaults = serde_json::json!({ "keybindings": { "Home": { "<Ctrl-d>": "Quit" } } });
+        flatten_values("", defaults, &ConfigSource::Default, &mut provenance);
+        let user = serde_json::json!({ "keybindings": { "Home": { "<ctrl-d>": "Suspend" } } });
+        let source = ConfigSource::User(PathBuf::from("config.json5"));
+        flatten_values("", user, &source, &mut provenance);
+        assert_eq!(
+            provenance.into_values().collect::<Vec<_>>(),
+            [ProvenanceEntry {
+                key: "keybindings.Home.<ctrl-d>".into(),
+                value: "Suspend".into(),
+                source,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_key_sequence_pattern() {
+        let pattern = key_sequence_pattern();
+        assert!(pattern.contains("[cC][tT][rR][lL]"));
+        assert!(pattern.find("[fF]10") < pattern.find("[fF]1|"));
+        assert_eq!(
+            Config::json_schema()["properties"]["keybindings"]["$ref"],
+            "#/$defs/KeyBindings"
+        );
+    }
+
     #[test]
     fn test_simple_keys() {
         assert_eq!(
@@ -579,6 +1036,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_key_sequence_round_trip() {
+        let keys = parse_key_sequence("<ctrl-a><f5>").unwrap();
+        assert_eq!(key_sequence_to_string(&keys), "<ctrl-a><f5>");
+        assert_eq!(
+            parse_key_sequence(&key_sequence_to_string(&keys)).unwrap(),
+            keys
+        );
+    }
+
+    #[test]
+    fn test_style_round_trip() {
+        let style = parse_style("bold underline red on color42");
+        assert_eq!(style_to_string(&style), "bold underline red on color42");
+        assert_eq!(parse_style(&style_to_string(&style)), style);
+    }
+
     #[test]
     fn test_invalid_keys() {
         assert!(parse_key_event("invalid-key").is_err());
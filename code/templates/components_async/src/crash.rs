@@ -0,0 +1,251 @@
+use std::{
+    collections::VecDeque,
+    env, fs,
+    path::PathBuf,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use color_eyre::Result;
+use ratatui::buffer::Buffer;
+use serde_json::Value;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{action::Action, cli, config, tui::Event};
+
+/// The number of actions and events kept for the crash report.
+const HISTORY_LEN: usize = 50;
+
+/// How often the rendered frame is copied for the crash report. Copying every frame would clone
+/// the whole buffer at the full frame rate, so the report shows a frame at most this old.
+const FRAME_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Config keys containing any of these are replaced with `"<redacted>"` in the crash report.
+const SECRET_MARKERS: [&str; 7] = [
+    "secret",
+    "token",
+    "password",
+    "passwd",
+    "api_key",
+    "apikey",
+    "credential",
+];
+
+static CONTEXT: LazyLock<Mutex<CrashContext>> = LazyLock::new(Default::default);
+
+/// Recent application state that is written to disk when the application panics.
+#[derive(Default)]
+struct CrashContext {
+    actions: VecDeque<String>,
+    events: VecDeque<String>,
+    config: Option<Value>,
+    last_frame: Option<Buffer>,
+    frame_recorded_at: Option<Instant>,
+}
+
+pub fn record_action(action: &Action) {
+    if matches!(action, Action::Tick | Action::Render) {
+        return;
+    }
+    if let Ok(mut context) = CONTEXT.lock() {
+        push_bounded(&mut context.actions, format!("{action:?}"));
+    }
+}
+
+pub fn record_event(event: &Event) {
+    if matches!(event, Event::Tick | Event::Render) {
+        return;
+    }
+    if let Ok(mut context) = CONTEXT.lock() {
+        push_bounded(&mut context.events, format!("{event:?}"));
+    }
+}
+
+pub fn record_config(config: &config::Config) {
+    let value = serde_json::to_value(config).map(redact).ok();
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.config = value;
+    }
+}
+
+pub fn record_frame(buffer: &Buffer) {
+    let Ok(mut context) = CONTEXT.try_lock() else {
+        return;
+    };
+    if context
+        .frame_recorded_at
+        .is_some_and(|at| at.elapsed() < FRAME_INTERVAL)
+    {
+        return;
+    }
+    context.frame_recorded_at = Some(Instant::now());
+    match &mut context.last_frame {
+        Some(last_frame) => {
+            // reuse the allocation of the previous snapshot
+            last_frame.area = buffer.area;
+            last_frame.content.clone_from(&buffer.content);
+        }
+        None => context.last_frame = Some(buffer.clone()),
+    }
+}
+
+fn push_bounded(history: &mut VecDeque<String>, entry: String) {
+    if history.len() == HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(entry);
+}
+
+/// Writes a crash bundle containing `report` and the recorded context to the data directory,
+/// returning the path of the bundle.
+///
+/// This is called from the panic hook, so it must not block on the context lock: if the panic
+/// happened while the lock was held, the recorded context is left out of the bundle.
+pub fn write_bundle(report: &str) -> Result<PathBuf> {
+    let directory = config::get_data_dir();
+    fs::create_dir_all(&directory)?;
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let path = directory.join(format!("crash-{timestamp}-{}.txt", std::process::id()));
+
+    let mut bundle = String::new();
+    section(&mut bundle, "Version", &cli::version());
+    section(&mut bundle, "Report", report);
+    section(&mut bundle, "Terminal", &terminal_info());
+    match CONTEXT.try_lock() {
+        Ok(context) => {
+            let actions = Vec::from(context.actions.clone()).join("\n");
+            let events = Vec::from(context.events.clone()).join("\n");
+            let config = context
+                .config
+                .as_ref()
+                .and_then(|config| serde_json::to_string_pretty(config).ok())
+                .unwrap_or_default();
+            let frame = context
+                .last_frame
+                .as_ref()
+                .map(buffer_to_string)
+                .unwrap_or_default();
+            section(&mut bundle, "Recent actions", &actions);
+            section(&mut bundle, "Recent events", &events);
+            section(&mut bundle, "Config", &config);
+            section(&mut bundle, "Last frame", &frame);
+        }
+        Err(_) => section(&mut bundle, "Context", "unavailable"),
+    }
+    fs::write(&path, bundle)?;
+    Ok(path)
+}
+
+fn section(bundle: &mut String, title: &str, body: &str) {
+    bundle.push_str(&format!("## {title}\n\n{}\n\n", body.trim_end()));
+}
+
+fn terminal_info() -> String {
+    let size = crossterm::terminal::size()
+        .map(|(width, height)| format!("{width}x{height}"))
+        .unwrap_or_else(|err| format!("unknown ({err})"));
+    let mut info = vec![format!("size: {size}")];
+    for var in ["TERM", "COLORTERM", "TERM_PROGRAM", "TERM_PROGRAM_VERSION"] {
+        let value = env::var(var).unwrap_or_default();
+        info.push(format!("{var}: {value}"));
+    }
+    info.join("\n")
+}
+
+/// Renders the symbols of a buffer as plain text, one line per row.
+fn buffer_to_string(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut lines = Vec::with_capacity(area.height as usize);
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        let mut skip = 0;
+        for x in area.left()..area.right() {
+            if skip > 0 {
+                // the previous symbol was wider than one cell
+                skip -= 1;
+                continue;
+            }
+            let symbol = buffer[(x, y)].symbol();
+            skip = symbol.width().saturating_sub(1);
+            line.push_str(symbol);
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    lines.join("\n")
+}
+
+fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                let lower = key.to_lowercase();
+                if SECRET_MARKERS.iter().any(|marker| lower.contains(marker)) {
+                    (key, Value::String("<redacted>".into()))
+                } else {
+                    (key, redact(value))
+                }
+            })
+            .collect(),
+        Value::Array(values) => values.into_iter().map(redact).collect(),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use ratatui::{layout::Rect, style::Style};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let config = json!({
+            "data_dir": "/tmp",
+            "github": { "api_token": "abc", "user": "me" },
+            "servers": [{ "password": "hunter2" }],
+        });
+        assert_eq!(
+            redact(config),
+            json!({
+                "data_dir": "/tmp",
+                "github": { "api_token": "<redacted>", "user": "me" },
+                "servers": [{ "password": "<redacted>" }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_buffer_to_string() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+        buffer.set_string(0, 0, "a😀b", Style::default());
+        buffer.set_string(0, 1, "cd", Style::default());
+        assert_eq!(buffer_to_string(&buffer), "a😀b\ncd");
+    }
+
+    #[test]
+    fn test_record_frame_is_throttled() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 2, 1));
+        buffer.set_string(0, 0, "a", Style::default());
+        record_frame(&buffer);
+        buffer.set_string(0, 0, "b", Style::default());
+        record_frame(&buffer);
+        let context = CONTEXT.lock().unwrap();
+        assert_eq!(
+            context.last_frame.as_ref().map(buffer_to_string).as_deref(),
+            Some("a")
+        );
+    }
+
+    #[test]
+    fn test_push_bounded() {
+        let mut history = VecDeque::new();
+        for i in 0..HISTORY_LEN + 2 {
+            push_bounded(&mut history, i.to_string());
+        }
+        assert_eq!(history.len(), HISTORY_LEN);
+        assert_eq!(history.front().map(String::as_str), Some("2"));
+    }
+}
@@ -16,7 +16,7 @@ pub fn init() -> Result<()> {
     eyre_hook.install()?;
     std::panic::set_hook(Box::new(move |panic_info| {
         if let Ok(mut t) = crate::tui::Tui::new() {
-            if let Err(r) = t.exit() {
+            if let Err(r) = t.restore() {
                 error!("Unable to exit Terminal: {:?}", r);
             }
         }
@@ -32,7 +32,9 @@ pub fn init() -> Result<()> {
             eprintln!("{}", panic_hook.panic_report(panic_info)); // prints color-eyre stack trace to stderr
         }
         let msg = format!("{}", panic_hook.panic_report(panic_info));
-        error!("Error: {}", strip_ansi_escapes::strip_str(msg));
+        let report = strip_ansi_escapes::strip_str(msg);
+        error!("Error: {}", report);
+        let crash_bundle = crate::crash::write_bundle(&report);
 
         #[cfg(debug_assertions)]
         {
@@ -44,6 +46,11 @@ pub fn init() -> Result<()> {
                 .create_panic_handler()(panic_info);
         }
 
+        match crash_bundle {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(err) => error!("Unable to write crash report: {:?}", err),
+        }
+
         std::process::exit(libc::EXIT_FAILURE);
     }));
     Ok(())
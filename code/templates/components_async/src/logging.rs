@@ -1,20 +1,46 @@
-use std::sync::LazyLock;
+use std::{
+    collections::VecDeque,
+    env,
+    fmt::{Debug, Write},
+    sync::{Arc, LazyLock, Mutex, OnceLock},
+    time::Duration,
+};
 
-use color_eyre::Result;
+use chrono::{DateTime, Local};
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+    span,
+};
 use tracing_error::ErrorLayer;
-use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, fmt, layer::Context, prelude::*, registry::LookupSpan, reload,
+};
 
 use crate::config;
 
+mod rotation;
+
+pub use rotation::{RotatingWriter, RotationPolicy};
+
 pub static LOG_ENV: LazyLock<String> =
     LazyLock::new(|| format!("{}_LOG_LEVEL", config::PROJECT_NAME.clone()));
-pub static LOG_FILE: LazyLock<String> = LazyLock::new(|| format!("{}.log", env!("CARGO_PKG_NAME")));
+pub static LOG_FORMAT_ENV: LazyLock<String> =
+    LazyLock::new(|| format!("{}_LOG_FORMAT", config::PROJECT_NAME.clone()));
+
+/// The most recent log events, shown by the log viewer component.
+pub static LOG_BUFFER: LazyLock<LogBuffer> = LazyLock::new(|| LogBuffer::new(1000));
+
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
 
-pub fn init() -> Result<()> {
+pub fn init(config: &config::LogConfig) -> Result<()> {
     let directory = config::get_data_dir();
-    std::fs::create_dir_all(directory.clone())?;
-    let log_path = directory.join(LOG_FILE.clone());
-    let log_file = std::fs::File::create(log_path)?;
+    let log_writer =
+        RotatingWriter::new(&directory, env!("CARGO_PKG_NAME"), rotation_policy(config))?;
     let env_filter = EnvFilter::builder().with_default_directive(tracing::Level::INFO.into());
     // If the `RUST_LOG` environment variable is set, use that as the default, otherwise use the
     // value of the `LOG_ENV` environment variable. If the `LOG_ENV` environment variable contains
@@ -22,16 +48,192 @@ pub fn init() -> Result<()> {
     let env_filter = env_filter
         .try_from_env()
         .or_else(|_| env_filter.with_env_var(LOG_ENV.clone()).from_env())?;
+    // The filter is wrapped in a reload layer so that the directives can be changed while the
+    // application is running (see `set_filter`).
+    let (env_filter, filter_handle) = reload::Layer::new(env_filter);
+    let _ = FILTER_HANDLE.set(filter_handle);
     let file_subscriber = fmt::layer()
         .with_file(true)
         .with_line_number(true)
-        .with_writer(log_file)
+        .with_writer(Mutex::new(log_writer))
         .with_target(false)
-        .with_ansi(false)
-        .with_filter(env_filter);
+        .with_ansi(false);
+    // Set the `LOG_FORMAT_ENV` environment variable to `json` to write one JSON object per line.
+    let file_subscriber = match env::var(LOG_FORMAT_ENV.clone()).as_deref() {
+        Ok("json") => file_subscriber.json().boxed(),
+        Ok("text") | Err(_) => file_subscriber.boxed(),
+        Ok(format) => bail!("unknown log format `{format}`, expected `text` or `json`"),
+    };
     tracing_subscriber::registry()
+        .with(env_filter)
         .with(file_subscriber)
+        .with(LOG_BUFFER.clone())
         .with(ErrorLayer::default())
         .try_init()?;
     Ok(())
 }
+
+/// The rotation policy set by the `log` config keys, using the defaults for unset ones.
+fn rotation_policy(config: &config::LogConfig) -> RotationPolicy {
+    let mut policy = RotationPolicy::default();
+    if let Some(max_size) = config.max_size {
+        policy.max_size = max_size;
+    }
+    if let Some(max_age) = config.max_age {
+        policy.max_age = Duration::from_secs(max_age);
+    }
+    if let Some(retention) = config.retention {
+        policy.retention = retention;
+    }
+    policy
+}
+
+/// Replaces the active filter directives, e.g. `"debug,tokio=warn"`.
+pub fn set_filter(directives: &str) -> Result<()> {
+    let filter = EnvFilter::try_new(directives)?;
+    let handle = FILTER_HANDLE
+        .get()
+        .ok_or_else(|| eyre!("logging is not initialized"))?;
+    handle.reload(filter)?;
+    Ok(())
+}
+
+/// Returns the active filter directives.
+pub fn current_filter() -> Option<String> {
+    FILTER_HANDLE
+        .get()?
+        .with_current(|filter| filter.to_string())
+        .ok()
+}
+
+/// A log event captured by the [`LogBuffer`].
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    pub fields: String,
+    /// The spans the event was recorded in, from the outermost to the innermost.
+    pub spans: Vec<String>,
+}
+
+/// A `tracing` layer that keeps the most recent events in memory.
+#[derive(Clone)]
+pub struct LogBuffer {
+    entries: Arc<Mutex<VecDeque<LogEntry>>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Returns a copy of the buffered entries, oldest first.
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.entries
+            .lock()
+            .map(|entries| entries.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn push(&self, entry: LogEntry) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.len() == self.capacity {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+    }
+}
+
+/// The formatted fields of a span, stored in the span's extensions.
+struct SpanFields(String);
+
+impl<S> Layer<S> for LogBuffer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        span.extensions_mut().insert(SpanFields(visitor.fields));
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            let mut visitor = FieldVisitor {
+                fields: std::mem::take(fields),
+                ..Default::default()
+            };
+            values.record(&mut visitor);
+            *fields = visitor.fields;
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| match span.extensions().get::<SpanFields>() {
+                        Some(SpanFields(fields)) if !fields.is_empty() => {
+                            format!("{}{{{fields}}}", span.name())
+                        }
+                        _ => span.name().to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let metadata = event.metadata();
+        self.push(LogEntry {
+            timestamp: Local::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+            spans,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            if !self.fields.is_empty() {
+                self.fields.push(' ');
+            }
+            let _ = write!(self.fields, "{}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use chrono::Local;
+
+/// When to start a new log file and how many old ones to keep.
+#[derive(Debug, Clone)]
+pub struct RotationPolicy {
+    /// Start a new file once the current one is larger than this many bytes.
+    pub max_size: u64,
+    /// Start a new file once the current one is older than this.
+    pub max_age: Duration,
+    /// The number of log files to keep in the log directory, including the current one.
+    pub retention: usize,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_size: 10 * 1024 * 1024,
+            max_age: Duration::from_secs(24 * 60 * 60),
+            retention: 10,
+        }
+    }
+}
+
+/// A writer that writes to a per-session log file and rotates it according to a
+/// [`RotationPolicy`].
+///
+/// Files are named `{prefix}-{timestamp}-{pid}.log`, and rotated files of the same session get a
+/// sequence number: `{prefix}-{timestamp}-{pid}.1.log`.
+pub struct RotatingWriter {
+    directory: PathBuf,
+    prefix: String,
+    session: String,
+    policy: RotationPolicy,
+    file: File,
+    size: u64,
+    opened_at: SystemTime,
+    sequence: usize,
+}
+
+impl RotatingWriter {
+    pub fn new(directory: &Path, prefix: &str, policy: RotationPolicy) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        let session = format!(
+            "{prefix}-{}-{}",
+            Local::now().format("%Y%m%d-%H%M%S"),
+            std::process::id()
+        );
+        let path = directory.join(format!("{session}.log"));
+        let writer = Self {
+            directory: directory.to_path_buf(),
+            prefix: prefix.to_string(),
+            session,
+            policy,
+            file: File::create(path)?,
+            size: 0,
+            opened_at: SystemTime::now(),
+            sequence: 0,
+        };
+        writer.remove_old_files()?;
+        Ok(writer)
+    }
+
+    /// The path of the file currently being written to.
+    pub fn path(&self) -> PathBuf {
+        let name = match self.sequence {
+            0 => format!("{}.log", self.session),
+            n => format!("{}.{n}.log", self.session),
+        };
+        self.directory.join(name)
+    }
+
+    fn should_rotate(&self) -> bool {
+        let age = self.opened_at.elapsed().unwrap_or_default();
+        self.size >= self.policy.max_size || age >= self.policy.max_age
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.sequence += 1;
+        self.file = File::create(self.path())?;
+        self.size = 0;
+        self.opened_at = SystemTime::now();
+        self.remove_old_files()
+    }
+
+    /// Deletes the oldest log files so that at most `retention` files are left. The current file
+    /// is always kept, even if another file looks newer because of clock skew.
+    ///
+    /// Another instance of the application may be writing to the same directory, so the files of
+    /// other sessions are only deleted once they have not been written to for `max_age`. By then
+    /// an instance that is still running starts a new file before it writes again.
+    fn remove_old_files(&self) -> io::Result<()> {
+        let current = self.path();
+        let mut files: Vec<(SystemTime, PathBuf)> = fs::read_dir(&self.directory)?
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.starts_with(&format!("{}-", self.prefix)) && name.ends_with(".log")
+            })
+            .filter(|entry| entry.path() != current)
+            .filter_map(|entry| {
+                let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+                Some((modified, entry.path()))
+            })
+            .collect();
+        files.sort_by(|a, b| b.cmp(a));
+        // the current file counts towards the retention
+        let keep = self.policy.retention.saturating_sub(1);
+        let own_files = format!("{}.", self.session);
+        for (modified, path) in files.into_iter().skip(keep) {
+            let own = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(&own_files));
+            let idle = modified
+                .elapsed()
+                .is_ok_and(|age| age >= self.policy.max_age);
+            if !own && !idle {
+                continue;
+            }
+            match fs::remove_file(path) {
+                // the other instance may have deleted it first
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for RotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn log_files(directory: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_rotates_by_size_and_keeps_retention() -> io::Result<()> {
+        let directory = std::env::temp_dir().join(format!("rotation-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let policy = RotationPolicy {
+            max_size: 10,
+            retention: 2,
+            ..Default::default()
+        };
+        let mut writer = RotatingWriter::new(&directory, "app", policy)?;
+        let session = writer.session.clone();
+        for _ in 0..4 {
+            writer.write_all(b"0123456789")?;
+        }
+        assert_eq!(writer.path(), directory.join(format!("{session}.3.log")));
+        assert_eq!(
+            log_files(&directory),
+            vec![format!("{session}.2.log"), format!("{session}.3.log")]
+        );
+        fs::remove_dir_all(&directory)
+    }
+
+    #[test]
+    fn test_keeps_current_file_when_others_look_newer() -> io::Result<()> {
+        let directory =
+            std::env::temp_dir().join(format!("rotation-skew-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory)?;
+        let future = SystemTime::now() + Duration::from_secs(60 * 60);
+        for name in ["app-old-1.log", "app-old-2.log"] {
+            File::create(directory.join(name))?.set_modified(future)?;
+        }
+        let policy = RotationPolicy {
+            retention: 2,
+            ..Default::default()
+        };
+        let writer = RotatingWriter::new(&directory, "app", policy)?;
+        let session = writer.session.clone();
+        assert!(writer.path().exists());
+        // files written to in the future may belong to a running instance, so they are kept too
+        assert_eq!(
+            log_files(&directory),
+            vec![
+                format!("{session}.log"),
+                "app-old-1.log".to_string(),
+                "app-old-2.log".to_string()
+            ]
+        );
+        fs::remove_dir_all(&directory)
+    }
+
+    #[test]
+    fn test_keeps_files_of_running_instances() -> io::Result<()> {
+        let directory =
+            std::env::temp_dir().join(format!("rotation-instances-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory)?;
+        let policy = RotationPolicy {
+            retention: 1,
+            ..Default::default()
+        };
+        let idle = SystemTime::now() - policy.max_age - Duration::from_secs(60);
+        File::create(directory.join("app-idle.log"))?.set_modified(idle)?;
+        File::create(directory.join("app-running.log"))?;
+        let writer = RotatingWriter::new(&directory, "app", policy)?;
+        let session = writer.session.clone();
+        assert_eq!(
+            log_files(&directory),
+            vec![format!("{session}.log"), "app-running.log".to_string()]
+        );
+        fs::remove_dir_all(&directory)
+    }
+}
@@ -3,24 +3,39 @@ use clap::Parser;
 use cli::Cli;
 use color_eyre::Result;
 
-use crate::app::App;
+use crate::{
+    app::App,
+    config::{Config, set_data_dir},
+};
 
 mod action;
 mod app;
 mod cli;
+mod clipboard;
 mod components;
 mod config;
+mod crash;
 mod errors;
 mod logging;
+mod router;
+mod session;
 mod tui;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     crate::errors::init()?;
-    crate::logging::init()?;
 
     let args = Cli::parse();
-    let mut app = App::new(args.tick_rate, args.frame_rate)?;
+    // subcommands exit right away, so they don't get a log file that would rotate out the logs of
+    // real sessions
+    if let Some(command) = args.command {
+        return command.run(&args.overrides);
+    }
+    let config = Config::with_overrides(&args.overrides)?;
+    // the logs, the session and crash reports all go to the configured data directory
+    set_data_dir(config.config.data_dir.clone());
+    crate::logging::init(&config.log)?;
+    let mut app = App::new(args.tick_rate, args.frame_rate, config)?;
     app.run().await?;
     Ok(())
 }
@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashMap};
+
+use color_eyre::{Result, eyre::eyre};
+use ratatui::layout::Size;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::debug;
+
+use crate::{action::Action, app::Mode, components::Component, config::Config};
+
+/// The parameters of a route, e.g. the id of the item shown by a detail screen.
+pub type Params = BTreeMap<String, String>;
+
+type Factory = Box<dyn Fn(&Params) -> Result<Vec<Box<dyn Component>>>>;
+
+/// A screen and the parameters it was opened with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Route {
+    pub name: String,
+    pub params: Params,
+}
+
+struct ScreenDefinition {
+    mode: Mode,
+    factory: Factory,
+}
+
+/// Switches between screens, keeping a history for `Action::Back` and `Action::Forward`.
+///
+/// Screens are registered by name with the keybinding mode that is active while they are shown
+/// and a factory for their components. A screen is only constructed when it is first navigated
+/// to, and is kept while its route is in the history, so going back returns to it unchanged.
+/// Screens that drop out of the history are closed rather than dropped, so that they still save
+/// their state and shut down when the application quits.
+pub struct Router {
+    definitions: HashMap<String, ScreenDefinition>,
+    screens: HashMap<Route, Vec<Box<dyn Component>>>,
+    /// The components of the screens that can no longer be reached through the history.
+    closed: Vec<Box<dyn Component>>,
+    current: Option<Route>,
+    back: Vec<Route>,
+    forward: Vec<Route>,
+    action_tx: UnboundedSender<Action>,
+    config: Config,
+    size: Size,
+}
+
+impl Router {
+    pub fn new(action_tx: UnboundedSender<Action>, config: Config) -> Self {
+        Self {
+            definitions: HashMap::new(),
+            screens: HashMap::new(),
+            closed: Vec::new(),
+            current: None,
+            back: Vec::new(),
+            forward: Vec::new(),
+            action_tx,
+            config,
+            size: Size::default(),
+        }
+    }
+
+    /// Registers a screen, replacing any screen with the same name.
+    pub fn register<F>(&mut self, name: &str, mode: Mode, factory: F)
+    where
+        F: Fn(&Params) -> Result<Vec<Box<dyn Component>>> + 'static,
+    {
+        let definition = ScreenDefinition {
+            mode,
+            factory: Box::new(factory),
+        };
+        self.definitions.insert(name.to_string(), definition);
+    }
+
+    /// The route of the screen that is shown.
+    pub fn current(&self) -> Option<&Route> {
+        self.current.as_ref()
+    }
+
+    /// The keybinding mode of the screen that is shown.
+    pub fn mode(&self) -> Mode {
+        self.current
+            .as_ref()
+            .and_then(|route| self.definitions.get(&route.name))
+            .map(|definition| definition.mode)
+            .unwrap_or_default()
+    }
+
+    /// The components of the screen that is shown.
+    pub fn components_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Component>> {
+        self.current
+            .as_ref()
+            .and_then(|route| self.screens.get_mut(route))
+            .into_iter()
+            .flatten()
+    }
+
+    /// The components of all constructed screens, including the ones in the history and the closed
+    /// ones. The closed screens come first, so that the state saved by a screen that is still open
+    /// takes precedence.
+    pub fn all_components_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Component>> {
+        self.closed
+            .iter_mut()
+            .chain(self.screens.values_mut().flatten())
+    }
+
+    /// Sets the size that new screens are initialized with.
+    pub fn resize(&mut self, size: Size) {
+        self.size = size;
+    }
+
+    /// Shows the screen `name`, constructing it if needed. This clears the forward history.
+    pub fn navigate(&mut self, name: &str, params: Params) -> Result<()> {
+        let route = Route {
+            name: name.to_string(),
+            params,
+        };
+        if self.current.as_ref() == Some(&route) {
+            return Ok(());
+        }
+        self.open(&route)?;
+        if let Some(previous) = self.current.replace(route) {
+            self.back.push(previous);
+        }
+        self.forward.clear();
+        self.close_unreachable();
+        Ok(())
+    }
+
+    /// Returns to the previous screen, returning whether there was one.
+    pub fn back(&mut self) -> Result<bool> {
+        let Some(route) = self.back.pop() else {
+            return Ok(false);
+        };
+        self.open(&route)?;
+        if let Some(previous) = self.current.replace(route) {
+            self.forward.push(previous);
+        }
+        Ok(true)
+    }
+
+    /// Undoes the last `back`, returning whether there was a screen to go forward to.
+    pub fn forward(&mut self) -> Result<bool> {
+        let Some(route) = self.forward.pop() else {
+            return Ok(false);
+        };
+        self.open(&route)?;
+        if let Some(previous) = self.current.replace(route) {
+            self.back.push(previous);
+        }
+        Ok(true)
+    }
+
+    /// Constructs the screen for `route` unless it already exists.
+    fn open(&mut self, route: &Route) -> Result<()> {
+        if self.screens.contains_key(route) {
+            return Ok(());
+        }
+        let definition = self
+            .definitions
+            .get(&route.name)
+            .ok_or_else(|| eyre!("unknown route `{}`", route.name))?;
+        debug!("Constructing screen {route:?}");
+        let mut components = (definition.factory)(&route.params)?;
+        for component in components.iter_mut() {
+            component.register_action_handler(self.action_tx.clone())?;
+            component.register_config_handler(self.config.clone())?;
+            component.init(self.size)?;
+        }
+        self.screens.insert(route.clone(), components);
+        Ok(())
+    }
+
+    /// Closes the screens that can no longer be reached through the history.
+    fn close_unreachable(&mut self) {
+        let (reachable, unreachable): (HashMap<_, _>, HashMap<_, _>) =
+            std::mem::take(&mut self.screens)
+                .into_iter()
+                .partition(|(route, _)| {
+                    self.current.as_ref() == Some(route)
+                        || self.back.contains(route)
+                        || self.forward.contains(route)
+                });
+        self.screens = reachable;
+        self.closed.extend(unreachable.into_values().flatten());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use ratatui::{Frame, layout::Rect};
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    struct Empty;
+
+    impl Component for Empty {
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn router() -> Router {
+        let (tx, _) = mpsc::unbounded_channel();
+        let mut router = Router::new(tx, Config::default());
+        router.register("list", Mode::Home, |_| Ok(vec![Box::new(Empty)]));
+        router.register("detail", Mode::Logs, |_| Ok(vec![Box::new(Empty)]));
+        router
+    }
+
+    fn current(router: &Router) -> &str {
+        router.current().map_or("", |route| route.name.as_str())
+    }
+
+    #[test]
+    fn test_back_and_forward() {
+        let mut router = router();
+        router.navigate("list", Params::new()).unwrap();
+        let params = Params::from([("id".to_string(), "1".to_string())]);
+        router.navigate("detail", params).unwrap();
+        assert_eq!(router.mode(), Mode::Logs);
+        assert!(router.back().unwrap());
+        assert_eq!(current(&router), "list");
+        assert!(!router.back().unwrap());
+        assert!(router.forward().unwrap());
+        assert_eq!(current(&router), "detail");
+        assert_eq!(router.screens.len(), 2);
+    }
+
+    #[test]
+    fn test_navigate_drops_forward_history() {
+        let mut router = router();
+        router.navigate("list", Params::new()).unwrap();
+        router.navigate("detail", Params::new()).unwrap();
+        router.back().unwrap();
+        router
+            .navigate("list", Params::from([("page".into(), "2".into())]))
+            .unwrap();
+        assert!(!router.forward().unwrap());
+        assert_eq!(router.screens.len(), 2);
+        // the detail screen is closed but still saved and shut down on quit
+        assert_eq!(router.closed.len(), 1);
+        assert_eq!(router.all_components_mut().count(), 3);
+        assert!(router.navigate("missing", Params::new()).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{config::get_data_dir, router::Params};
+
+/// The version of the session file format. Bump it when the saved state changes in a way that old
+/// files can't be read anymore: sessions with another version are discarded.
+pub const SESSION_VERSION: u32 = 1;
+
+/// The state saved between runs of the application.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    /// The name and parameters of the screen that was shown.
+    pub route: Option<(String, Params)>,
+    /// The state of each component, by the component's session key.
+    pub components: BTreeMap<String, Value>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            version: SESSION_VERSION,
+            route: None,
+            components: BTreeMap::new(),
+        }
+    }
+}
+
+impl Session {
+    pub fn path() -> PathBuf {
+        get_data_dir().join("session.json")
+    }
+
+    /// Loads the saved session, falling back to the default session when there is none or when it
+    /// can't be read.
+    pub fn load() -> Self {
+        Self::load_from(&Self::path())
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        match serde_json::from_str::<Self>(&contents) {
+            Ok(session) if session.version == SESSION_VERSION => session,
+            Ok(session) => {
+                warn!(
+                    "Ignoring session with version {} (expected {SESSION_VERSION})",
+                    session.version
+                );
+                Self::default()
+            }
+            Err(err) => {
+                warn!("Ignoring unreadable session {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    /// Saves the session, replacing the previous one atomically.
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::path())
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn session_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("session-test-{}", std::process::id()))
+            .join(format!("{name}.json"))
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let path = session_path("round-trip");
+        let mut session = Session {
+            route: Some(("home".into(), Params::new())),
+            ..Session::default()
+        };
+        session
+            .components
+            .insert("home".into(), json!({ "counter": 3 }));
+        session.save_to(&path).unwrap();
+        assert_eq!(Session::load_from(&path), session);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_missing_session_loads_default() {
+        let path = session_path("missing");
+        assert_eq!(Session::load_from(&path), Session::default());
+    }
+
+    #[test]
+    fn test_corrupt_session_loads_default() {
+        let path = session_path("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{ \"version\": 1, \"compo").unwrap();
+        assert_eq!(Session::load_from(&path), Session::default());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_other_version_loads_default() {
+        let path = session_path("other-version");
+        let mut session = Session {
+            version: SESSION_VERSION + 1,
+            ..Session::default()
+        };
+        session
+            .components
+            .insert("home".into(), json!({ "counter": 3 }));
+        session.save_to(&path).unwrap();
+        assert_eq!(Session::load_from(&path), Session::default());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,10 +1,28 @@
 // ANCHOR: all
-use std::collections::HashMap;
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use ratatui::widgets::ListState;
+use serde_json::{Map, Value};
+
+use crate::{
+    format::Format,
+    input::TextField,
+    search,
+    tree::{self, NodePath, PathSegment, SortMode, TreeRow, ValueType},
+};
 
 // ANCHOR: screen_modes
 pub enum CurrentScreen {
     Main,
     Editing,
+    Deleting,
+    Searching,
     Exiting,
 }
 // ANCHOR_END: screen_modes
@@ -18,11 +36,24 @@ pub enum CurrentlyEditing {
 
 // ANCHOR: app_fields
 pub struct App {
-    pub key_input: String,              // the currently being edited json key.
-    pub value_input: String,            // the currently being edited json value.
-    pub pairs: HashMap<String, String>, // The representation of our key and value pairs with serde Serialize support
+    pub key_input: TextField,          // the currently being edited json key.
+    pub value_input: TextField,        // the currently being edited json value.
+    pub value_type: ValueType,         // the type the currently edited value will be saved as.
+    pub root: Value, // The json document being edited. This is always an object, whose values can be nested objects and arrays.
+    pub collapsed: HashSet<NodePath>, // the objects and arrays whose children are hidden in the tree view.
+    pub list_state: ListState,        // the selected row of the tree view.
+    pub sort: SortMode,               // the order of the keys in the tree view and in the output.
+    pub search: String, // the text the tree view is filtered by, or an empty string to show everything.
+    pub indent: usize, // the number of spaces the output is indented with, or 0 for compact output.
+    pub format: Format, // the format of the file, or the format the document is printed in on exit.
+    pub parent: NodePath, // the object or array that the pair being edited will be added to.
+    pub editing: Option<NodePath>, // the existing value that the pair being edited replaces, if it is not a new one.
+    pub overwrite_key: Option<String>, // the duplicate key that the user confirmed to overwrite.
     pub current_screen: CurrentScreen, // the current screen the user is looking at, and will later determine what is rendered.
     pub currently_editing: Option<CurrentlyEditing>, // the optional state containing which of the key or value pair the user is editing. It is an option, because when the user is not directly editing a key-value pair, this will be set to `None`.
+    pub path: Option<PathBuf>, // the file the pairs are loaded from and saved to, if one was given on the command line.
+    pub dirty: bool,           // whether there are changes that have not been saved to `path` yet.
+    pub message: Option<String>, // a message for the user, such as the result of the last save.
 }
 // ANCHOR_END: app_fields
 
@@ -30,29 +61,426 @@ pub struct App {
 impl App {
     pub fn new() -> App {
         App {
-            key_input: String::new(),
-            value_input: String::new(),
-            pairs: HashMap::new(),
+            key_input: TextField::default(),
+            value_input: TextField::default(),
+            value_type: ValueType::default(),
+            root: Value::Object(Map::new()),
+            collapsed: HashSet::new(),
+            list_state: ListState::default(),
+            sort: SortMode::default(),
+            search: String::new(),
+            indent: 2,
+            format: Format::default(),
+            parent: NodePath::new(),
+            editing: None,
+            overwrite_key: None,
             current_screen: CurrentScreen::Main,
             currently_editing: None,
+            path: None,
+            dirty: false,
+            message: None,
         }
     }
     // ANCHOR_END: impl_new
 
+    // ANCHOR: open
+    /// Creates an app that edits the file at `path`. The file's top-level object is loaded when
+    /// the file exists, otherwise it is created on the first save. The file is read and written
+    /// in `format`, or in the format its extension suggests when that is `None`.
+    pub fn open(path: PathBuf, format: Option<Format>) -> Result<App, Box<dyn Error>> {
+        let mut app = App::new();
+        app.format = format
+            .or_else(|| Format::from_path(&path))
+            .unwrap_or_default();
+        if path.exists() {
+            let contents = fs::read_to_string(&path)?;
+            app.root = app
+                .format
+                .read(&contents)
+                .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        }
+        app.path = Some(path);
+        Ok(app)
+    }
+    // ANCHOR_END: open
+
+    // ANCHOR: save
+    /// Saves the document to `path`, replacing the file atomically so that a failed write never
+    /// leaves a truncated file behind.
+    pub fn save(&mut self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Err(io::Error::other("no file to save to"));
+        };
+        let output = self.export().map_err(io::Error::other)?;
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp = path.with_file_name(format!(".{file_name}.tmp"));
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(output.as_bytes())?;
+        // keep the permissions of the file being replaced
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        // the contents have to be on disk before the rename, or a crash could leave an empty file
+        file.sync_all()?;
+        fs::rename(&temp, path)?;
+        self.dirty = false;
+        Ok(())
+    }
+    // ANCHOR_END: save
+
+    // ANCHOR: export
+    /// The document in the app's format, with the keys ordered by the sort mode.
+    pub fn export(&self) -> Result<String, String> {
+        let value = tree::sorted(&self.root, self.sort);
+        self.format.write(&value, self.indent)
+    }
+    // ANCHOR_END: export
+
+    // ANCHOR: rows
+    /// The rows of the tree view. While searching, these are the matches and the values that
+    /// contain them, including the ones inside collapsed values.
+    pub fn rows(&self) -> Vec<TreeRow<'_>> {
+        if self.search.is_empty() {
+            return tree::rows(&self.root, &self.collapsed, self.sort);
+        }
+        let rows = tree::rows(&self.root, &HashSet::new(), self.sort);
+        search::filter_rows(rows, &self.search)
+    }
+    // ANCHOR_END: rows
+
+    /// The indices of the rows that match the search.
+    pub fn matches(&self) -> Vec<usize> {
+        if self.search.is_empty() {
+            return Vec::new();
+        }
+        self.rows()
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| search::row_matches(row, &self.search))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Changes the search and selects its first match.
+    pub fn set_search(&mut self, search: String) {
+        let selected = self.selected_path();
+        self.search = search;
+        if self.search.is_empty() {
+            let row = selected.and_then(|path| self.rows().iter().position(|row| row.path == path));
+            self.list_state.select(row);
+        } else {
+            self.list_state.select(self.matches().first().copied());
+        }
+    }
+
+    // ANCHOR: select_match
+    /// Selects the next match after the selected row, or the previous one before it, wrapping
+    /// around at the end of the list.
+    pub fn select_match(&mut self, forward: bool) {
+        let matches = self.matches();
+        let selected = self.list_state.selected();
+        let next = if forward {
+            let after = matches.iter().find(|&&i| selected.is_none_or(|s| i > s));
+            after.or(matches.first())
+        } else {
+            let before = matches
+                .iter()
+                .rev()
+                .find(|&&i| selected.is_none_or(|s| i < s));
+            before.or(matches.last())
+        };
+        if let Some(&index) = next {
+            self.list_state.select(Some(index));
+        }
+    }
+    // ANCHOR_END: select_match
+
+    // ANCHOR: toggle_sort
+    /// Switches to the next sort mode, keeping the same value selected.
+    pub fn toggle_sort(&mut self) {
+        let selected = self.selected_path();
+        self.sort = self.sort.next();
+        if let Some(path) = selected {
+            let row = self.rows().iter().position(|row| row.path == path);
+            self.list_state.select(row);
+        }
+    }
+    // ANCHOR_END: toggle_sort
+
+    /// The path of the value in the selected row.
+    pub fn selected_path(&self) -> Option<NodePath> {
+        let selected = self.list_state.selected()?;
+        self.rows().into_iter().nth(selected).map(|row| row.path)
+    }
+
+    pub fn select_next(&mut self) {
+        let last = self.rows().len().saturating_sub(1);
+        let next = self.list_state.selected().map_or(0, |i| (i + 1).min(last));
+        self.list_state.select(Some(next));
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self
+            .list_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(1));
+        self.list_state.select(Some(previous));
+    }
+
+    // ANCHOR: toggle_collapsed
+    /// Shows or hides the children of the selected object or array.
+    pub fn toggle_collapsed(&mut self) {
+        if let Some(path) = self.selected_path() {
+            if !self.collapsed.remove(&path) {
+                self.set_collapsed(true);
+            }
+        }
+    }
+
+    pub fn set_collapsed(&mut self, collapsed: bool) {
+        let Some(path) = self.selected_path() else {
+            return;
+        };
+        if !collapsed {
+            self.collapsed.remove(&path);
+        } else if matches!(
+            tree::get(&self.root, &path),
+            Some(Value::Object(_) | Value::Array(_))
+        ) {
+            self.collapsed.insert(path);
+        }
+    }
+    // ANCHOR_END: toggle_collapsed
+
+    // ANCHOR: start_adding
+    /// Opens the editing popup for a new value. It is added to the selected object or array, or
+    /// next to the selected value when that is neither.
+    pub fn start_adding(&mut self) {
+        let mut parent = self.selected_path().unwrap_or_default();
+        if !matches!(
+            tree::get(&self.root, &parent),
+            Some(Value::Object(_) | Value::Array(_))
+        ) {
+            parent.pop();
+        }
+        self.open_popup(parent, None);
+    }
+    // ANCHOR_END: start_adding
+
+    // ANCHOR: start_editing
+    /// Opens the editing popup for the selected value, filled in with its key and value.
+    pub fn start_editing(&mut self) {
+        let Some(path) = self.selected_path() else {
+            return;
+        };
+        let Some(value) = tree::get(&self.root, &path) else {
+            return;
+        };
+        self.value_type = ValueType::of(value);
+        let value_input = tree::input_text(value);
+        let mut parent = path.clone();
+        let key_input = match parent.pop() {
+            Some(PathSegment::Key(key)) => key,
+            _ => String::new(),
+        };
+        self.open_popup(parent, Some(path));
+        self.key_input.set_value(key_input);
+        self.value_input.set_value(value_input);
+    }
+    // ANCHOR_END: start_editing
+
+    fn open_popup(&mut self, parent: NodePath, editing: Option<NodePath>) {
+        if editing.is_none() {
+            self.value_type = ValueType::default();
+        }
+        self.parent = parent;
+        self.editing = editing;
+        self.overwrite_key = None;
+        self.key_input.clear();
+        self.value_input.clear();
+        self.current_screen = CurrentScreen::Editing;
+        self.currently_editing = if self.parent_is_array() {
+            Some(CurrentlyEditing::Value)
+        } else {
+            Some(CurrentlyEditing::Key)
+        };
+    }
+
+    /// The field of the popup that is being typed into.
+    pub fn editing_field(&mut self) -> Option<&mut TextField> {
+        match self.currently_editing {
+            Some(CurrentlyEditing::Key) => Some(&mut self.key_input),
+            Some(CurrentlyEditing::Value) => Some(&mut self.value_input),
+            None => None,
+        }
+    }
+
+    /// Inserts text pasted into the terminal into the field or search being edited.
+    pub fn paste(&mut self, text: &str) {
+        match self.current_screen {
+            CurrentScreen::Editing => {
+                if let Some(field) = self.editing_field() {
+                    field.insert_str(text);
+                }
+            }
+            CurrentScreen::Searching => {
+                let search = format!("{}{}", self.search, text.replace(['\r', '\n'], ""));
+                self.set_search(search);
+            }
+            _ => {}
+        }
+    }
+
+    /// Closes the editing popup without saving.
+    pub fn cancel_editing(&mut self) {
+        self.current_screen = CurrentScreen::Main;
+        self.currently_editing = None;
+        self.editing = None;
+    }
+
+    /// Whether the pair being edited is added to an array, which means that it has no key.
+    pub fn parent_is_array(&self) -> bool {
+        matches!(tree::get(&self.root, &self.parent), Some(Value::Array(_)))
+    }
+
+    /// The reason the entered value can't be saved, if there is one.
+    pub fn value_error(&self) -> Option<String> {
+        self.value_type.parse(self.value_input.value()).err()
+    }
+
+    /// Whether saving would replace another value with the same key.
+    pub fn is_duplicate_key(&self) -> bool {
+        let Some(Value::Object(map)) = tree::get(&self.root, &self.parent) else {
+            return false;
+        };
+        let renamed = match self.editing.as_ref().and_then(|path| path.last()) {
+            Some(PathSegment::Key(key)) => key != self.key_input.value(),
+            _ => true,
+        };
+        renamed && map.contains_key(self.key_input.value())
+    }
+
+    /// The value that saving the popup results in. Objects and arrays whose type is not changed
+    /// keep their children.
+    fn entered_value(&self) -> Result<Value, String> {
+        let existing = self
+            .editing
+            .as_ref()
+            .and_then(|path| tree::get(&self.root, path))
+            .filter(|value| ValueType::of(value) == self.value_type);
+        match existing {
+            Some(value @ (Value::Object(_) | Value::Array(_))) => Ok(value.clone()),
+            _ => self.value_type.parse(self.value_input.value()),
+        }
+    }
+
     // ANCHOR: save_key_value
-    pub fn save_key_value(&mut self) {
-        self.pairs
-            .insert(self.key_input.clone(), self.value_input.clone());
+    pub fn save_key_value(&mut self) -> Result<(), String> {
+        let value = self.entered_value()?;
+        let key = self.key_input.value();
+        if self.is_duplicate_key() && self.overwrite_key.as_deref() != Some(key) {
+            // the first attempt only warns, saving again confirms the overwrite
+            self.overwrite_key = Some(key.to_string());
+            return Err(format!("`{key}` already exists"));
+        }
+        let path = match self.editing.take() {
+            Some(path) if self.parent_is_array() => {
+                if let Some(item) = tree::get_mut(&mut self.root, &path) {
+                    *item = value;
+                }
+                path
+            }
+            editing => {
+                // a renamed key keeps its place among the other keys
+                let position = editing.and_then(|path| {
+                    let position = self.position(&path);
+                    tree::remove(&mut self.root, &path);
+                    position
+                });
+                self.insert(value, position)?
+            }
+        };
+        self.dirty = true;
+        self.message = None;
+        self.collapsed.remove(&self.parent);
+        let row = self.rows().iter().position(|row| row.path == path);
+        self.list_state.select(row);
 
-        self.key_input = String::new();
-        self.value_input = String::new();
+        self.key_input.clear();
+        self.value_input.clear();
         self.currently_editing = None;
+        Ok(())
     }
     // ANCHOR_END: save_key_value
 
+    /// The index of the value at `path` among the entries of its parent object.
+    fn position(&self, path: &[PathSegment]) -> Option<usize> {
+        let (PathSegment::Key(key), parent) = path.split_last()? else {
+            return None;
+        };
+        let Some(Value::Object(map)) = tree::get(&self.root, parent) else {
+            return None;
+        };
+        map.keys().position(|k| k == key)
+    }
+
+    /// Adds a value to the parent of the popup, at `position` if it is an object and a position
+    /// is given or at the end otherwise. Returns the path of the value.
+    fn insert(&mut self, value: Value, position: Option<usize>) -> Result<NodePath, String> {
+        let mut path = self.parent.clone();
+        match tree::get_mut(&mut self.root, &self.parent) {
+            Some(Value::Object(map)) => {
+                let key = self.key_input.value().to_string();
+                match position {
+                    Some(index) => {
+                        // an existing key is moved, so it can't go past the last entry
+                        let len = map.len() - usize::from(map.contains_key(&key));
+                        map.shift_insert(index.min(len), key, value);
+                    }
+                    None => {
+                        map.insert(key, value);
+                    }
+                }
+                path.push(PathSegment::Key(self.key_input.value().to_string()));
+            }
+            Some(Value::Array(items)) => {
+                items.push(value);
+                path.push(PathSegment::Index(items.len() - 1));
+            }
+            _ => return Err("the parent is not an object or array".to_string()),
+        }
+        Ok(path)
+    }
+
+    // ANCHOR: delete_selected
+    /// Removes the selected value and everything in it.
+    pub fn delete_selected(&mut self) {
+        let Some(path) = self.selected_path() else {
+            return;
+        };
+        if tree::remove(&mut self.root, &path).is_none() {
+            return;
+        }
+        self.collapsed = self
+            .collapsed
+            .drain()
+            .filter_map(|collapsed| shift_after_removal(collapsed, &path))
+            .collect();
+        self.dirty = true;
+        self.message = None;
+        let last = self.rows().len().checked_sub(1);
+        let selected = self.list_state.selected().zip(last);
+        self.list_state
+            .select(selected.map(|(i, last)| i.min(last)));
+    }
+    // ANCHOR_END: delete_selected
+
     // ANCHOR: toggle_editing
     pub fn toggle_editing(&mut self) {
-        if let Some(edit_mode) = &self.currently_editing {
+        if self.parent_is_array() {
+            // array items have no key to switch to
+            self.currently_editing = Some(CurrentlyEditing::Value);
+        } else if let Some(edit_mode) = &self.currently_editing {
             match edit_mode {
                 CurrentlyEditing::Key => self.currently_editing = Some(CurrentlyEditing::Value),
                 CurrentlyEditing::Value => self.currently_editing = Some(CurrentlyEditing::Key),
@@ -63,12 +491,31 @@ impl App {
     }
     // ANCHOR_END: toggle_editing
 
-    // ANCHOR: print_json
-    pub fn print_json(&self) -> serde_json::Result<()> {
-        let output = serde_json::to_string(&self.pairs)?;
-        println!("{output}");
+    // ANCHOR: print_output
+    pub fn print_output(&self) -> Result<(), String> {
+        let output = self.export()?;
+        print!("{output}");
         Ok(())
     }
-    // ANCHOR_END: print_json
+    // ANCHOR_END: print_output
+}
+
+/// Updates a path for the removal of the value at `removed`. Paths inside the removed value are
+/// gone, and the indices of later items in the same array move down by one.
+fn shift_after_removal(mut path: NodePath, removed: &[PathSegment]) -> Option<NodePath> {
+    if path.starts_with(removed) {
+        return None;
+    }
+    let (PathSegment::Index(removed_index), parent) = removed.split_last()? else {
+        return Some(path);
+    };
+    if path.starts_with(parent) {
+        if let Some(PathSegment::Index(index)) = path.get_mut(parent.len()) {
+            if *index > *removed_index {
+                *index -= 1;
+            }
+        }
+    }
+    Some(path)
 }
 // ANCHOR_END: all
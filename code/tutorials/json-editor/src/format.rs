@@ -0,0 +1,407 @@
+// ANCHOR: all
+use std::{collections::HashMap, fmt, path::Path, str::FromStr};
+
+use serde::Serialize;
+use serde_json::{ser::PrettyFormatter, Map, Number, Serializer, Value};
+
+/// The file formats that documents can be read from and written to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    #[default]
+    PrettyJson,
+    Yaml,
+    Toml,
+    /// `KEY=VALUE` lines, as read by dotenv libraries. Nested values are flattened into keys
+    /// joined with `__`, and reading a file nests them again. Characters that can't be used in
+    /// variable names and empty objects and arrays are lost on the way.
+    Dotenv,
+}
+
+impl Format {
+    pub const ALL: [Format; 5] = [
+        Format::Json,
+        Format::PrettyJson,
+        Format::Yaml,
+        Format::Toml,
+        Format::Dotenv,
+    ];
+
+    /// The format of a file, going by its extension.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let file_name = path.file_name()?.to_string_lossy();
+        if file_name.starts_with(".env") {
+            return Some(Format::Dotenv);
+        }
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::PrettyJson),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "env" => Some(Format::Dotenv),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> Format {
+        let index = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(self) -> Format {
+        let index = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    // ANCHOR: read_write
+    /// Reads a document, whose top-level value must be an object.
+    pub fn read(self, text: &str) -> Result<Value, String> {
+        let value = match self {
+            Format::Json | Format::PrettyJson => {
+                serde_json::from_str(text).map_err(|err| err.to_string())?
+            }
+            Format::Yaml => serde_norway::from_str(text).map_err(|err| err.to_string())?,
+            Format::Toml => toml::from_str(text).map_err(|err| err.to_string())?,
+            Format::Dotenv => read_dotenv(text)?,
+        };
+        match value {
+            Value::Object(_) => Ok(value),
+            _ => Err(format!("the {self} document does not contain an object")),
+        }
+    }
+
+    /// Writes a document, ending with a newline. Pretty JSON is indented by `indent` spaces, or
+    /// written compactly when it is 0.
+    pub fn write(self, value: &Value, indent: usize) -> Result<String, String> {
+        let output = match self {
+            Format::Json => serde_json::to_string(value).map_err(|err| err.to_string())?,
+            Format::PrettyJson if indent == 0 => {
+                serde_json::to_string(value).map_err(|err| err.to_string())?
+            }
+            Format::PrettyJson => write_pretty_json(value, indent)?,
+            Format::Yaml => serde_norway::to_string(value).map_err(|err| err.to_string())?,
+            Format::Toml => {
+                if let Some(path) = find_null(value, String::new()) {
+                    return Err(format!("`{path}` is null, which TOML can't represent"));
+                }
+                toml::to_string_pretty(value).map_err(|err| err.to_string())?
+            }
+            Format::Dotenv => write_dotenv(value)?,
+        };
+        Ok(format!("{}\n", output.trim_end()))
+    }
+    // ANCHOR_END: read_write
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Format::Json => "JSON",
+            Format::PrettyJson => "pretty JSON",
+            Format::Yaml => "YAML",
+            Format::Toml => "TOML",
+            Format::Dotenv => "dotenv",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Format, String> {
+        match name {
+            "json" => Ok(Format::Json),
+            "pretty-json" => Ok(Format::PrettyJson),
+            "yaml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "dotenv" | "env" => Ok(Format::Dotenv),
+            _ => Err(format!(
+                "unknown format `{name}`, expected json, pretty-json, yaml, toml or dotenv"
+            )),
+        }
+    }
+}
+
+fn write_pretty_json(value: &Value, indent: usize) -> Result<String, String> {
+    let indent = " ".repeat(indent);
+    let mut output = Vec::new();
+    let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+    value
+        .serialize(&mut Serializer::with_formatter(&mut output, formatter))
+        .map_err(|err| err.to_string())?;
+    Ok(String::from_utf8(output).expect("serde_json only writes UTF-8"))
+}
+
+/// The dotted path of the first null in `value`.
+fn find_null(value: &Value, path: String) -> Option<String> {
+    match value {
+        Value::Null => Some(path),
+        Value::Object(map) => map.iter().find_map(|(key, child)| {
+            let path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            find_null(child, path)
+        }),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .find_map(|(index, child)| find_null(child, format!("{path}[{index}]"))),
+        _ => None,
+    }
+}
+
+/// Writes the scalars of `value` as `KEY=VALUE` lines. Characters that can't be used in variable
+/// names are replaced with `_`, and two keys that end up with the same name are an error.
+fn write_dotenv(value: &Value) -> Result<String, String> {
+    let mut entries = Vec::new();
+    flatten(value, String::new(), &mut entries);
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut lines = Vec::new();
+    for (key, value) in entries {
+        let name = env_name(&key);
+        if let Some(other) = names.insert(name.clone(), key.clone()) {
+            return Err(format!(
+                "`{other}` and `{key}` would both be written as {name}"
+            ));
+        }
+        lines.push(format!("{name}={}", env_value(value)));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Collects the scalars in `value` with their keys joined by `__`.
+fn flatten<'a>(value: &'a Value, prefix: String, entries: &mut Vec<(String, &'a Value)>) {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{prefix}__{key}")
+        }
+    };
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                flatten(child, join(key), entries);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                flatten(child, join(&index.to_string()), entries);
+            }
+        }
+        scalar => entries.push((prefix, scalar)),
+    }
+}
+
+/// Turns a key into a variable name, which consists of ASCII letters, digits and underscores and
+/// doesn't start with a digit.
+fn env_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn env_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => {
+            let escaped = text
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('$', "\\$");
+            format!("\"{escaped}\"")
+        }
+        Value::Null => String::new(),
+        scalar => scalar.to_string(),
+    }
+}
+
+/// Reads `KEY=VALUE` lines, skipping blank lines and comments. This undoes `write_dotenv`: keys
+/// joined with `__` become nested objects, and objects whose keys are the indices `0`, `1`, ... in
+/// order become arrays again.
+fn read_dotenv(text: &str) -> Result<Value, String> {
+    let mut map = Map::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected KEY=VALUE", number + 1));
+        };
+        insert_nested(&mut map, key.trim(), read_env_value(value.trim()))
+            .map_err(|err| format!("line {}: {err}", number + 1))?;
+    }
+    // the top level stays an object, even if its keys look like indices
+    let map = map
+        .into_iter()
+        .map(|(key, value)| (key, restore_arrays(value)))
+        .collect();
+    Ok(Value::Object(map))
+}
+
+/// Inserts a value under a key joined with `__`, creating the objects on the way.
+fn insert_nested(map: &mut Map<String, Value>, key: &str, value: Value) -> Result<(), String> {
+    let mut map = map;
+    let mut segments = key.split("__");
+    let mut segment = segments.next().unwrap_or_default();
+    for next in segments {
+        let child = map
+            .entry(segment)
+            .or_insert_with(|| Value::Object(Map::new()));
+        let Value::Object(child) = child else {
+            return Err(format!(
+                "`{key}` is inside `{segment}`, which already has a value"
+            ));
+        };
+        map = child;
+        segment = next;
+    }
+    if let Some(Value::Object(_)) = map.get(segment) {
+        return Err(format!("`{key}` already has nested values"));
+    }
+    // like in a shell, a later assignment replaces an earlier one
+    map.insert(segment.to_string(), value);
+    Ok(())
+}
+
+/// Turns the objects whose keys are `0`, `1`, ... in order back into the arrays that `flatten`
+/// wrote them from.
+fn restore_arrays(value: Value) -> Value {
+    let Value::Object(map) = value else {
+        return value;
+    };
+    let is_array = map
+        .keys()
+        .enumerate()
+        .all(|(index, key)| *key == index.to_string());
+    let entries = map
+        .into_iter()
+        .map(|(key, value)| (key, restore_arrays(value)));
+    if is_array {
+        Value::Array(entries.map(|(_, value)| value).collect())
+    } else {
+        Value::Object(entries.collect())
+    }
+}
+
+/// Reads a value written by `env_value`. Quoted values are strings. Unquoted values are numbers,
+/// booleans or null (when empty) if they look like one, and strings otherwise.
+fn read_env_value(value: &str) -> Value {
+    if value.starts_with(['"', '\'']) {
+        return Value::String(read_env_string(value));
+    }
+    let value = read_env_string(value);
+    match value.as_str() {
+        "" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => value
+            .parse::<Number>()
+            .map(Value::Number)
+            .unwrap_or(Value::String(value)),
+    }
+}
+
+fn read_env_string(value: &str) -> String {
+    if let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let mut unescaped = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some(other) => unescaped.push(other),
+                None => unescaped.push('\\'),
+            }
+        }
+        return unescaped;
+    }
+    if let Some(quoted) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return quoted.to_string();
+    }
+    // unquoted values end at a comment
+    match value.find(" #") {
+        Some(index) => value[..index].trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
+
+// ANCHOR_END: all
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_dotenv_round_trip() {
+        let value = json!({
+            "db": { "host": "a \"b\" $c", "port": 5432, "user": null },
+            "9 lives": [true, "7"],
+        });
+        let output = Format::Dotenv.write(&value, 0).unwrap();
+        assert_eq!(
+            output,
+            "db__host=\"a \\\"b\\\" \\$c\"\ndb__port=5432\ndb__user=\n_9_lives__0=true\n_9_lives__1=\"7\"\n"
+        );
+        // only the key that was not a valid variable name changes
+        let read = Format::Dotenv.read(&output).unwrap();
+        assert_eq!(
+            read,
+            json!({
+                "db": { "host": "a \"b\" $c", "port": 5432, "user": null },
+                "_9_lives": [true, "7"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_dotenv() {
+        let text = "# comment\nexport NAME=plain text # note\nZIP=01234\nLIST__1=b\nLIST__0=a\n";
+        assert_eq!(
+            Format::Dotenv.read(text).unwrap(),
+            json!({ "NAME": "plain text", "ZIP": "01234", "LIST": { "1": "b", "0": "a" } })
+        );
+        assert_eq!(
+            Format::Dotenv.read("A=1\nA__B=2"),
+            Err("line 2: `A__B` is inside `A`, which already has a value".to_string())
+        );
+        assert_eq!(
+            Format::Dotenv.read("A__B=1\nA=2"),
+            Err("line 2: `A` already has nested values".to_string())
+        );
+    }
+
+    #[test]
+    fn test_write_reports_unrepresentable_values() {
+        let value = json!({ "a": { "b": null } });
+        assert_eq!(
+            Format::Toml.write(&value, 0),
+            Err("`a.b` is null, which TOML can't represent".to_string())
+        );
+        let value = json!({ "a-b": 1, "a_b": 2 });
+        assert!(Format::Dotenv.write(&value, 0).is_err());
+    }
+
+    #[test]
+    fn test_yaml_and_toml_round_trip() {
+        let value = json!({ "name": "x", "list": [1, 2], "table": { "key with space": true } });
+        for format in [Format::Yaml, Format::Toml] {
+            let output = format.write(&value, 0).unwrap();
+            assert_eq!(format.read(&output).unwrap(), value, "{format}");
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// ANCHOR: all
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthChar;
+
+// ANCHOR: text_field
+/// A single line of editable text with a cursor.
+#[derive(Debug, Default, Clone)]
+pub struct TextField {
+    value: String,
+    /// The position of the cursor, in chars.
+    cursor: usize,
+}
+
+impl TextField {
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Replaces the text, moving the cursor to its end.
+    pub fn set_value(&mut self, value: String) {
+        self.cursor = value.chars().count();
+        self.value = value;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_value(String::new());
+    }
+
+    /// Inserts a char at the cursor.
+    pub fn insert(&mut self, c: char) {
+        let index = self.byte_index(self.cursor);
+        self.value.insert(index, c);
+        self.cursor += 1;
+    }
+
+    /// Inserts pasted text at the cursor. Line breaks become spaces, as the field is one line.
+    pub fn insert_str(&mut self, text: &str) {
+        for c in text.chars().filter(|c| *c != '\r') {
+            self.insert(if c == '\n' { ' ' } else { c });
+        }
+    }
+
+    // ANCHOR_END: text_field
+
+    /// Deletes the char before the cursor.
+    pub fn delete_backward(&mut self) {
+        if self.cursor > 0 {
+            self.delete(self.cursor - 1..self.cursor);
+        }
+    }
+
+    /// Handles the keys for moving the cursor and deleting text, returning whether the key was
+    /// one of them:
+    ///
+    /// - `Left`/`Right` and `Home`/`End` (or `Ctrl-a`/`Ctrl-e`) move the cursor
+    /// - `Ctrl` or `Alt` with `Left`/`Right` (or `Alt-b`/`Alt-f`) move by words
+    /// - `Delete` deletes the char after the cursor, `Ctrl-w` or `Alt-Backspace` the word before it
+    /// - `Ctrl-u` and `Ctrl-k` delete everything before and after the cursor
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let by_word = key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let len = self.value.chars().count();
+        match key.code {
+            KeyCode::Left if by_word => self.cursor = self.previous_word(),
+            KeyCode::Right if by_word => self.cursor = self.next_word(),
+            KeyCode::Char('b') if alt => self.cursor = self.previous_word(),
+            KeyCode::Char('f') if alt => self.cursor = self.next_word(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(len),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::End => self.cursor = len,
+            KeyCode::Char('e') if ctrl => self.cursor = len,
+            KeyCode::Backspace if alt => self.delete(self.previous_word()..self.cursor),
+            KeyCode::Char('w') if ctrl => self.delete(self.previous_word()..self.cursor),
+            KeyCode::Backspace => self.delete_backward(),
+            KeyCode::Delete if self.cursor < len => self.delete(self.cursor..self.cursor + 1),
+            KeyCode::Delete => {}
+            KeyCode::Char('u') if ctrl => self.delete(0..self.cursor),
+            KeyCode::Char('k') if ctrl => self.delete(self.cursor..len),
+            _ => return false,
+        }
+        true
+    }
+
+    // ANCHOR: scroll
+    /// The column of the cursor relative to the start of the text.
+    pub fn cursor_column(&self) -> u16 {
+        let width: usize = self
+            .value
+            .chars()
+            .take(self.cursor)
+            .map(|c| c.width().unwrap_or(0))
+            .sum();
+        width as u16
+    }
+
+    /// How many columns the text has to be scrolled by to keep the cursor in a box of `width`.
+    pub fn scroll(&self, width: u16) -> u16 {
+        let columns = self.cursor_column().saturating_sub(width.saturating_sub(1));
+        // a wide character can't be cut in half, so scroll past all of it
+        let mut scroll = 0;
+        for c in self.value.chars() {
+            if scroll >= columns {
+                break;
+            }
+            scroll += c.width().unwrap_or(0) as u16;
+        }
+        scroll
+    }
+    // ANCHOR_END: scroll
+
+    /// Removes the chars in `range`, leaving the cursor at its start.
+    fn delete(&mut self, range: std::ops::Range<usize>) {
+        let start = self.byte_index(range.start);
+        let end = self.byte_index(range.end);
+        self.value.replace_range(start..end, "");
+        self.cursor = range.start;
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.value.len(), |(index, _)| index)
+    }
+
+    /// The start of the word before the cursor.
+    fn previous_word(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut index = self.cursor;
+        while index > 0 && !chars[index - 1].is_alphanumeric() {
+            index -= 1;
+        }
+        while index > 0 && chars[index - 1].is_alphanumeric() {
+            index -= 1;
+        }
+        index
+    }
+
+    /// The end of the word after the cursor.
+    fn next_word(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut index = self.cursor;
+        while index < chars.len() && !chars[index].is_alphanumeric() {
+            index += 1;
+        }
+        while index < chars.len() && chars[index].is_alphanumeric() {
+            index += 1;
+        }
+        index
+    }
+}
+
+// ANCHOR_END: all
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(field: &mut TextField, code: KeyCode, modifiers: KeyModifiers) {
+        assert!(field.handle_key(KeyEvent::new(code, modifiers)));
+    }
+
+    #[test]
+    fn test_edit_at_cursor() {
+        let mut field = TextField::default();
+        field.set_value("hello wörld".to_string());
+        press(&mut field, KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(field.cursor, 6);
+        field.insert_str("big\n");
+        assert_eq!(field.value(), "hello big wörld");
+        press(&mut field, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(field.value(), "hello wörld");
+        press(&mut field, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut field, KeyCode::Delete, KeyModifiers::NONE);
+        press(&mut field, KeyCode::Right, KeyModifiers::ALT);
+        field.insert('!');
+        assert_eq!(field.value(), "ello! wörld");
+        press(&mut field, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(field.value(), "ello!");
+    }
+
+    #[test]
+    fn test_scroll_keeps_cursor_visible() {
+        let mut field = TextField::default();
+        field.set_value("0123456789".to_string());
+        assert_eq!(field.scroll(4), 7);
+        press(&mut field, KeyCode::Home, KeyModifiers::NONE);
+        assert_eq!(field.scroll(4), 0);
+    }
+
+    #[test]
+    fn test_scroll_past_wide_characters() {
+        let mut field = TextField::default();
+        field.set_value("日本語".to_string());
+        // scrolling by 3 columns would cut 本 in half
+        assert_eq!(field.scroll(4), 4);
+        assert_eq!(field.cursor_column() - field.scroll(4), 2);
+    }
+}
@@ -1,10 +1,13 @@
 // ANCHOR: all
-use std::{error::Error, io};
+use std::{error::Error, io, path::PathBuf};
 
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
-        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+        event::{
+            self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste,
+            EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        },
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
@@ -12,26 +15,47 @@ use ratatui::{
 };
 
 mod app;
+mod format;
+mod input;
+mod search;
+mod tree;
 mod ui;
 use crate::{
     app::{App, CurrentScreen, CurrentlyEditing},
+    format::Format,
     ui::ui,
 };
 
 // ANCHOR: main_all
 // ANCHOR: setup_boilerplate
 fn main() -> Result<(), Box<dyn Error>> {
+    // open the file given on the command line before taking over the terminal, so that errors
+    // are printed normally
+    let args = parse_args()?;
+    let mut app = match args.path {
+        Some(path) => App::open(path, args.format)?,
+        None => App::new(),
+    };
+    app.indent = args.indent;
+    if let (None, Some(format)) = (&app.path, args.format) {
+        app.format = format;
+    }
+
     // setup terminal
     enable_raw_mode()?;
     let mut stderr = io::stderr(); // This is a special case. Normally using stdout is fine
-    execute!(stderr, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stderr,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     // ANCHOR_END: setup_boilerplate
     // ANCHOR: application_startup
     let backend = CrosstermBackend::new(stderr);
     let mut terminal = Terminal::new(backend)?;
 
-    // create app and run it
-    let mut app = App::new();
+    // run the app
     let res = run_app(&mut terminal, &mut app);
     // ANCHOR_END: application_startup
 
@@ -41,7 +65,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
     // ANCHOR_END: ending_boilerplate
@@ -49,7 +74,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     // ANCHOR: final_print
     if let Ok(do_print) = res {
         if do_print {
-            app.print_json()?;
+            app.print_output()?;
         }
     } else if let Err(err) = res {
         println!("{err:?}");
@@ -60,6 +85,41 @@ fn main() -> Result<(), Box<dyn Error>> {
 // ANCHOR_END: final_print
 // ANCHOR_END: main_all
 
+// ANCHOR: parse_args
+/// The command line: `json-editor [--indent <spaces>] [--format <format>] [file]`.
+struct Args {
+    path: Option<PathBuf>,
+    /// The indent of pretty JSON, where 0 writes compact JSON.
+    indent: usize,
+    /// The format of the file, or of the output when there is no file.
+    format: Option<Format>,
+}
+
+fn parse_args() -> Result<Args, Box<dyn Error>> {
+    let mut parsed = Args {
+        path: None,
+        indent: 2,
+        format: None,
+    };
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--indent" {
+            let value = args.next().ok_or("--indent needs a number of spaces")?;
+            parsed.indent = value
+                .to_str()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| format!("invalid indent: {}", value.to_string_lossy()))?;
+        } else if arg == "--format" {
+            let value = args.next().ok_or("--format needs a format")?;
+            parsed.format = Some(value.to_string_lossy().parse()?);
+        } else {
+            parsed.path = Some(PathBuf::from(arg));
+        }
+    }
+    Ok(parsed)
+}
+// ANCHOR_END: parse_args
+
 // ANCHOR: run_app_all
 // ANCHOR: run_method_signature
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<bool>
@@ -74,18 +134,64 @@ where
 
         // ANCHOR: event_poll
         // ANCHOR: main_screen
-        if let Event::Key(key) = event::read()? {
+        let event = event::read()?;
+        if let Event::Paste(text) = &event {
+            app.paste(text);
+        }
+        if let Event::Key(key) = event {
             if key.kind == event::KeyEventKind::Release {
                 // Skip events that are not KeyEventKind::Press
                 continue;
             }
             match app.current_screen {
                 CurrentScreen::Main => match key.code {
+                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        save(app);
+                    }
                     KeyCode::Char('e') => {
-                        app.current_screen = CurrentScreen::Editing;
-                        app.currently_editing = Some(CurrentlyEditing::Key);
+                        app.start_adding();
+                    }
+                    KeyCode::Enter => {
+                        app.start_editing();
+                    }
+                    KeyCode::Char('d') if app.selected_path().is_some() => {
+                        app.current_screen = CurrentScreen::Deleting;
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        app.select_next();
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.select_previous();
+                    }
+                    KeyCode::Char(' ') => {
+                        app.toggle_collapsed();
+                    }
+                    KeyCode::Char('s') => {
+                        app.toggle_sort();
+                    }
+                    KeyCode::Char('/') => {
+                        app.current_screen = CurrentScreen::Searching;
+                    }
+                    KeyCode::Char('n') => {
+                        app.select_match(true);
+                    }
+                    KeyCode::Char('N') => {
+                        app.select_match(false);
+                    }
+                    KeyCode::Esc => {
+                        app.set_search(String::new());
+                    }
+                    KeyCode::Left | KeyCode::Char('h') => {
+                        app.set_collapsed(true);
+                    }
+                    KeyCode::Right | KeyCode::Char('l') => {
+                        app.set_collapsed(false);
                     }
                     KeyCode::Char('q') => {
+                        // a file without changes can be closed straight away
+                        if app.path.is_some() && !app.dirty {
+                            return Ok(false);
+                        }
                         app.current_screen = CurrentScreen::Exiting;
                     }
                     _ => {}
@@ -93,15 +199,76 @@ where
                 // ANCHOR_END: main_screen
                 // ANCHOR: exiting_screen
                 CurrentScreen::Exiting => match key.code {
+                    KeyCode::Char('y') if app.path.is_some() => {
+                        if save(app) {
+                            return Ok(false);
+                        }
+                        app.current_screen = CurrentScreen::Main;
+                    }
                     KeyCode::Char('y') => {
-                        return Ok(true);
+                        // only leave when the document can be written in the chosen format
+                        match app.export() {
+                            Ok(_) => return Ok(true),
+                            Err(err) => app.message = Some(err),
+                        }
                     }
                     KeyCode::Char('n') | KeyCode::Char('q') => {
                         return Ok(false);
                     }
+                    KeyCode::Left if app.path.is_none() => {
+                        app.format = app.format.previous();
+                        app.message = None;
+                    }
+                    KeyCode::Right | KeyCode::Tab if app.path.is_none() => {
+                        app.format = app.format.next();
+                        app.message = None;
+                    }
+                    KeyCode::Esc => {
+                        app.current_screen = CurrentScreen::Main;
+                    }
                     _ => {}
                 },
                 // ANCHOR_END: exiting_screen
+                // ANCHOR: searching_screen
+                CurrentScreen::Searching => match key.code {
+                    // shortcuts like ctrl-c are not part of the search
+                    KeyCode::Char(value)
+                        if !key
+                            .modifiers
+                            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                    {
+                        let mut search = app.search.clone();
+                        search.push(value);
+                        app.set_search(search);
+                    }
+                    KeyCode::Backspace => {
+                        let mut search = app.search.clone();
+                        search.pop();
+                        app.set_search(search);
+                    }
+                    KeyCode::Enter => {
+                        // keep the list filtered to step through the matches
+                        app.current_screen = CurrentScreen::Main;
+                    }
+                    KeyCode::Esc => {
+                        app.set_search(String::new());
+                        app.current_screen = CurrentScreen::Main;
+                    }
+                    _ => {}
+                },
+                // ANCHOR_END: searching_screen
+                // ANCHOR: deleting_screen
+                CurrentScreen::Deleting => match key.code {
+                    KeyCode::Char('y') => {
+                        app.delete_selected();
+                        app.current_screen = CurrentScreen::Main;
+                    }
+                    KeyCode::Char('n') | KeyCode::Esc => {
+                        app.current_screen = CurrentScreen::Main;
+                    }
+                    _ => {}
+                },
+                // ANCHOR_END: deleting_screen
                 // ANCHOR: editing_enter
                 CurrentScreen::Editing if key.kind == KeyEventKind::Press => {
                     match key.code {
@@ -112,31 +279,26 @@ where
                                         app.currently_editing = Some(CurrentlyEditing::Value);
                                     }
                                     CurrentlyEditing::Value => {
-                                        app.save_key_value();
-                                        app.current_screen = CurrentScreen::Main;
+                                        // an invalid value or a duplicate key stays in the popup,
+                                        // which shows why
+                                        if app.save_key_value().is_ok() {
+                                            app.current_screen = CurrentScreen::Main;
+                                        }
                                     }
                                 }
                             }
                         }
                         // ANCHOR_END: editing_enter
                         // ANCHOR: backspace_editing
-                        KeyCode::Backspace => {
-                            if let Some(editing) = &app.currently_editing {
-                                match editing {
-                                    CurrentlyEditing::Key => {
-                                        app.key_input.pop();
-                                    }
-                                    CurrentlyEditing::Value => {
-                                        app.value_input.pop();
-                                    }
-                                }
+                        KeyCode::Backspace if !key.modifiers.contains(KeyModifiers::ALT) => {
+                            if let Some(field) = app.editing_field() {
+                                field.delete_backward();
                             }
                         }
                         // ANCHOR_END: backspace_editing
                         // ANCHOR: escape_editing
                         KeyCode::Esc => {
-                            app.current_screen = CurrentScreen::Main;
-                            app.currently_editing = None;
+                            app.cancel_editing();
                         }
                         // ANCHOR_END: escape_editing
                         // ANCHOR: tab_editing
@@ -144,21 +306,29 @@ where
                             app.toggle_editing();
                         }
                         // ANCHOR_END: tab_editing
+                        KeyCode::Up => {
+                            app.value_type = app.value_type.previous();
+                        }
+                        KeyCode::Down => {
+                            app.value_type = app.value_type.next();
+                        }
                         // ANCHOR: character_editing
-                        KeyCode::Char(value) => {
-                            if let Some(editing) = &app.currently_editing {
-                                match editing {
-                                    CurrentlyEditing::Key => {
-                                        app.key_input.push(value);
-                                    }
-                                    CurrentlyEditing::Value => {
-                                        app.value_input.push(value);
-                                    }
-                                }
+                        KeyCode::Char(value)
+                            if !key
+                                .modifiers
+                                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                        {
+                            if let Some(field) = app.editing_field() {
+                                field.insert(value);
                             }
                         }
                         // ANCHOR_END: character_editing
-                        _ => {}
+                        _ => {
+                            // moving the cursor and the other editing shortcuts
+                            if let Some(field) = app.editing_field() {
+                                field.handle_key(key);
+                            }
+                        }
                     }
                 }
                 _ => {}
@@ -169,4 +339,16 @@ where
 }
 // ANCHOR: run_app_all
 
+// ANCHOR: save
+/// Saves the app to its file, showing the outcome to the user. Returns whether the save succeeded.
+fn save(app: &mut App) -> bool {
+    let result = app.save();
+    app.message = Some(match &result {
+        Ok(()) => "Saved".to_string(),
+        Err(err) => format!("Failed to save: {err}"),
+    });
+    result.is_ok()
+}
+// ANCHOR_END: save
+
 // ANCHOR_END: all
@@ -0,0 +1,96 @@
+// ANCHOR: all
+use std::{collections::HashSet, ops::Range};
+
+use serde_json::Value;
+
+use crate::tree::{self, NodePath, PathSegment, TreeRow};
+
+/// The byte ranges of the case-insensitive occurrences of `query` in `text`.
+pub fn find_matches(text: &str, query: &str) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+    let mut start = 0;
+    while start < text.len() {
+        match match_at(&text[start..], query) {
+            Some(len) => {
+                matches.push(start..start + len);
+                start += len;
+            }
+            None => start += text[start..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    matches
+}
+
+/// The length in bytes of the match of `query` at the start of `text`, if there is one.
+fn match_at(text: &str, query: &str) -> Option<usize> {
+    let mut text_chars = text.char_indices();
+    let mut len = 0;
+    for query_char in query.chars() {
+        let (index, text_char) = text_chars.next()?;
+        if !text_char.to_lowercase().eq(query_char.to_lowercase()) {
+            return None;
+        }
+        len = index + text_char.len_utf8();
+    }
+    Some(len)
+}
+
+/// Whether the key or the value of a row contains `query`. Objects and arrays only match by key.
+pub fn row_matches(row: &TreeRow, query: &str) -> bool {
+    let key_matches = match row.path.last() {
+        Some(PathSegment::Key(key)) => !find_matches(key, query).is_empty(),
+        _ => false,
+    };
+    let value_matches = match row.value {
+        Value::Object(_) | Value::Array(_) => false,
+        scalar => !find_matches(&tree::input_text(scalar), query).is_empty(),
+    };
+    key_matches || value_matches
+}
+
+// ANCHOR: filter_rows
+/// Keeps the rows that match `query` and the objects and arrays that contain them, so that every
+/// match is shown with the keys that lead to it.
+pub fn filter_rows<'a>(rows: Vec<TreeRow<'a>>, query: &str) -> Vec<TreeRow<'a>> {
+    let mut shown: HashSet<NodePath> = HashSet::new();
+    for row in rows.iter().filter(|row| row_matches(row, query)) {
+        for len in 1..=row.path.len() {
+            shown.insert(row.path[..len].to_vec());
+        }
+    }
+    rows.into_iter()
+        .filter(|row| shown.contains(&row.path))
+        .collect()
+}
+// ANCHOR_END: filter_rows
+// ANCHOR_END: all
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::tree::SortMode;
+
+    #[test]
+    fn test_find_matches_ignores_case() {
+        assert_eq!(find_matches("Hello hello", "HELLO"), [0..5, 6..11]);
+        assert_eq!(find_matches("Grüße", "SSE"), []);
+        assert_eq!(find_matches("Grüße", "üß"), vec![2..6]);
+        assert_eq!(find_matches("text", ""), []);
+    }
+
+    #[test]
+    fn test_filter_rows_keeps_parents_of_matches() {
+        let root = json!({ "name": "x", "server": { "host": "example", "port": 80 } });
+        let rows = tree::rows(&root, &HashSet::new(), SortMode::Insertion);
+        let labels: Vec<String> = filter_rows(rows, "EXAMPLE")
+            .iter()
+            .map(TreeRow::label)
+            .collect();
+        assert_eq!(labels, ["server", "host"]);
+    }
+}
@@ -0,0 +1,353 @@
+// ANCHOR: all
+use std::{cmp::Ordering, collections::HashSet, fmt};
+
+use serde_json::{Map, Number, Value};
+
+// ANCHOR: node_path
+/// One step from a value to one of its children.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// The location of a value in the document, as the steps that lead to it from the root.
+pub type NodePath = Vec<PathSegment>;
+// ANCHOR_END: node_path
+
+/// The types that a new value can be given in the editing popup. Values of different types are
+/// sorted in this order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ValueType {
+    #[default]
+    String,
+    Number,
+    Bool,
+    Null,
+    Object,
+    Array,
+}
+
+impl ValueType {
+    const ALL: [ValueType; 6] = [
+        ValueType::String,
+        ValueType::Number,
+        ValueType::Bool,
+        ValueType::Null,
+        ValueType::Object,
+        ValueType::Array,
+    ];
+
+    pub fn of(value: &Value) -> ValueType {
+        match value {
+            Value::String(_) => ValueType::String,
+            Value::Number(_) => ValueType::Number,
+            Value::Bool(_) => ValueType::Bool,
+            Value::Null => ValueType::Null,
+            Value::Object(_) => ValueType::Object,
+            Value::Array(_) => ValueType::Array,
+        }
+    }
+
+    pub fn next(self) -> ValueType {
+        let index = Self::ALL.iter().position(|t| *t == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(self) -> ValueType {
+        let index = Self::ALL.iter().position(|t| *t == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Whether values of this type are typed in. Nulls, objects and arrays always start out empty.
+    pub fn has_input(self) -> bool {
+        matches!(
+            self,
+            ValueType::String | ValueType::Number | ValueType::Bool
+        )
+    }
+
+    // ANCHOR: parse
+    /// Turns the text entered for a value of this type into the value.
+    pub fn parse(self, input: &str) -> Result<Value, String> {
+        match self {
+            ValueType::String => Ok(Value::String(input.to_string())),
+            ValueType::Number => input
+                .trim()
+                .parse::<Number>()
+                .map(Value::Number)
+                .map_err(|_| format!("`{input}` is not a number")),
+            ValueType::Bool => match input.trim() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err("a boolean must be `true` or `false`".to_string()),
+            },
+            ValueType::Null => Ok(Value::Null),
+            ValueType::Object => Ok(Value::Object(Map::new())),
+            ValueType::Array => Ok(Value::Array(Vec::new())),
+        }
+    }
+    // ANCHOR_END: parse
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueType::String => "string",
+            ValueType::Number => "number",
+            ValueType::Bool => "bool",
+            ValueType::Null => "null",
+            ValueType::Object => "object",
+            ValueType::Array => "array",
+        };
+        f.write_str(name)
+    }
+}
+
+// ANCHOR: sort_mode
+/// The order in which the entries of objects are shown and written. Array items always keep their
+/// order, as it is part of their meaning.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    /// The order in which the keys were read or added.
+    #[default]
+    Insertion,
+    Key,
+    Value,
+}
+
+impl SortMode {
+    pub fn next(self) -> SortMode {
+        match self {
+            SortMode::Insertion => SortMode::Key,
+            SortMode::Key => SortMode::Value,
+            SortMode::Value => SortMode::Insertion,
+        }
+    }
+
+    fn sort(self, entries: &mut [(&String, &Value)]) {
+        match self {
+            SortMode::Insertion => {}
+            SortMode::Key => entries.sort_by_key(|&(key, _)| key),
+            SortMode::Value => entries.sort_by(|(_, a), (_, b)| compare_values(a, b)),
+        }
+    }
+}
+
+impl fmt::Display for SortMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SortMode::Insertion => "insertion order",
+            SortMode::Key => "key",
+            SortMode::Value => "value",
+        };
+        f.write_str(name)
+    }
+}
+
+// ANCHOR_END: sort_mode
+
+/// Orders values of the same type by their contents and other values by their type.
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => ValueType::of(a).cmp(&ValueType::of(b)),
+    }
+}
+
+// ANCHOR: tree_rows
+/// A value as it is shown in the tree view.
+pub struct TreeRow<'a> {
+    pub path: NodePath,
+    pub depth: usize,
+    pub value: &'a Value,
+}
+
+impl TreeRow<'_> {
+    /// The key of the value, or its index in brackets for array items.
+    pub fn label(&self) -> String {
+        match self.path.last() {
+            Some(PathSegment::Key(key)) => key.clone(),
+            Some(PathSegment::Index(index)) => format!("[{index}]"),
+            None => String::new(),
+        }
+    }
+}
+
+/// Flattens the values below `root` into the rows of the tree view, leaving out the children of
+/// collapsed objects and arrays.
+pub fn rows<'a>(
+    root: &'a Value,
+    collapsed: &HashSet<NodePath>,
+    sort: SortMode,
+) -> Vec<TreeRow<'a>> {
+    let mut rows = Vec::new();
+    push_rows(root, &mut NodePath::new(), collapsed, sort, &mut rows);
+    rows
+}
+
+fn push_rows<'a>(
+    value: &'a Value,
+    path: &mut NodePath,
+    collapsed: &HashSet<NodePath>,
+    sort: SortMode,
+    rows: &mut Vec<TreeRow<'a>>,
+) {
+    for (segment, child) in children(value, sort) {
+        path.push(segment);
+        rows.push(TreeRow {
+            path: path.clone(),
+            depth: path.len() - 1,
+            value: child,
+        });
+        if !collapsed.contains(path) {
+            push_rows(child, path, collapsed, sort, rows);
+        }
+        path.pop();
+    }
+}
+
+// ANCHOR_END: tree_rows
+
+/// The children of an object or array, with the segments that lead to them.
+fn children(value: &Value, sort: SortMode) -> Vec<(PathSegment, &Value)> {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            sort.sort(&mut entries);
+            entries
+                .into_iter()
+                .map(|(key, child)| (PathSegment::Key(key.clone()), child))
+                .collect()
+        }
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, child)| (PathSegment::Index(index), child))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn get<'a>(root: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(root, |value, segment| match (value, segment) {
+            (Value::Object(map), PathSegment::Key(key)) => map.get(key),
+            (Value::Array(items), PathSegment::Index(index)) => items.get(*index),
+            _ => None,
+        })
+}
+
+pub fn get_mut<'a>(root: &'a mut Value, path: &[PathSegment]) -> Option<&'a mut Value> {
+    path.iter()
+        .try_fold(root, |value, segment| match (value, segment) {
+            (Value::Object(map), PathSegment::Key(key)) => map.get_mut(key),
+            (Value::Array(items), PathSegment::Index(index)) => items.get_mut(*index),
+            _ => None,
+        })
+}
+
+/// A copy of `value` with the entries of all objects in it in the order of `sort`.
+pub fn sorted(value: &Value, sort: SortMode) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            sort.sort(&mut entries);
+            let map = entries
+                .into_iter()
+                .map(|(key, child)| (key.clone(), sorted(child, sort)))
+                .collect();
+            Value::Object(map)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| sorted(item, sort)).collect()),
+        scalar => scalar.clone(),
+    }
+}
+
+/// Removes the value at `path` from its parent, returning it.
+pub fn remove(root: &mut Value, path: &[PathSegment]) -> Option<Value> {
+    let (last, parent) = path.split_last()?;
+    match (get_mut(root, parent)?, last) {
+        (Value::Object(map), PathSegment::Key(key)) => map.shift_remove(key),
+        (Value::Array(items), PathSegment::Index(index)) if *index < items.len() => {
+            Some(items.remove(*index))
+        }
+        _ => None,
+    }
+}
+
+/// The text that is edited for a value: strings without their quotes, other scalars as JSON and
+/// nothing for objects and arrays.
+pub fn input_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Object(_) | Value::Array(_) => String::new(),
+        scalar => scalar.to_string(),
+    }
+}
+
+/// A one line summary of a value: scalars as JSON, objects and arrays by their size.
+pub fn summary(value: &Value) -> String {
+    match value {
+        Value::Object(map) => format!("{{{} keys}}", map.len()),
+        Value::Array(items) => format!("[{} items]", items.len()),
+        scalar => scalar.to_string(),
+    }
+}
+
+// ANCHOR_END: all
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_rows_skip_collapsed_children() {
+        let root = json!({ "a": { "b": 1 }, "c": [true, null] });
+        let collapsed = HashSet::from([vec![PathSegment::Key("a".to_string())]]);
+        let labels: Vec<String> = rows(&root, &collapsed, SortMode::Insertion)
+            .iter()
+            .map(TreeRow::label)
+            .collect();
+        assert_eq!(labels, ["a", "c", "[0]", "[1]"]);
+    }
+
+    #[test]
+    fn test_sorted() {
+        let root = json!({ "b": 2, "c": { "z": "a", "y": "b" }, "a": 3 });
+        let keys =
+            |value: &Value| -> Vec<String> { value.as_object().unwrap().keys().cloned().collect() };
+        assert_eq!(keys(&sorted(&root, SortMode::Insertion)), ["b", "c", "a"]);
+        let by_key = sorted(&root, SortMode::Key);
+        assert_eq!(keys(&by_key), ["a", "b", "c"]);
+        assert_eq!(keys(&by_key["c"]), ["y", "z"]);
+        let by_value = sorted(&root, SortMode::Value);
+        assert_eq!(keys(&by_value), ["b", "a", "c"]);
+        assert_eq!(keys(&by_value["c"]), ["z", "y"]);
+    }
+
+    #[test]
+    fn test_parse_validates_input() {
+        assert_eq!(ValueType::Number.parse(" 1.5 "), Ok(json!(1.5)));
+        assert!(ValueType::Number.parse("1.5.2").is_err());
+        assert_eq!(ValueType::Bool.parse("false"), Ok(json!(false)));
+        assert!(ValueType::Bool.parse("yes").is_err());
+        assert_eq!(ValueType::Array.parse("ignored"), Ok(json!([])));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut root = json!({ "a": [1, 2, 3] });
+        let path = [PathSegment::Key("a".to_string()), PathSegment::Index(1)];
+        assert_eq!(remove(&mut root, &path), Some(json!(2)));
+        assert_eq!(remove(&mut root, &path[..1]), Some(json!([1, 3])));
+        assert_eq!(remove(&mut root, &path[..1]), None);
+    }
+}
@@ -1,16 +1,28 @@
 // ANCHOR: all
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    layout::{Constraint, Direction, Layout, Position, Rect},
+    style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Wrap,
+    },
     Frame,
 };
 
-use crate::app::{App, CurrentScreen, CurrentlyEditing};
+use serde_json::Value;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app::{App, CurrentScreen, CurrentlyEditing},
+    format::Format,
+    input::TextField,
+    search,
+    tree::{self, PathSegment, SortMode},
+};
 
 // ANCHOR: method_sig
-pub fn ui(frame: &mut Frame, app: &App) {
+pub fn ui(frame: &mut Frame, app: &mut App) {
     // ANCHOR_END: method_sig
     // Create the layout sections.
     // ANCHOR: ui_layout
@@ -29,27 +41,102 @@ pub fn ui(frame: &mut Frame, app: &App) {
         .borders(Borders::ALL)
         .style(Style::default());
 
-    let title = Paragraph::new(Text::styled(
-        "Create New Json",
-        Style::default().fg(Color::Green),
-    ))
-    .block(title_block);
+    let mut title_spans = vec![match &app.path {
+        Some(path) => Span::styled(
+            path.display().to_string(),
+            Style::default().fg(Color::Green),
+        ),
+        None => Span::styled("Create New Json", Style::default().fg(Color::Green)),
+    }];
+    if app.dirty {
+        title_spans.push(Span::styled(" [+]", Style::default().fg(Color::Yellow)));
+    }
+    if app.sort != SortMode::Insertion {
+        title_spans.push(Span::styled(
+            format!(" (sorted by {})", app.sort),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+    if let Some(message) = &app.message {
+        title_spans.push(Span::styled(
+            format!(" - {message}"),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    let title = Paragraph::new(Line::from(title_spans)).block(title_block);
 
     frame.render_widget(title, chunks[0]);
     // ANCHOR_END: title_paragraph
     // ANCHOR: key_value_list
+    let searching = matches!(app.current_screen, CurrentScreen::Searching);
+    let [list_area, search_area] = if searching || !app.search.is_empty() {
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(chunks[1])
+    } else {
+        [chunks[1], Rect::default()]
+    };
+    let [list_area, scrollbar_area] =
+        Layout::horizontal([Constraint::Min(1), Constraint::Length(1)]).areas(list_area);
+
     let mut list_items = Vec::<ListItem>::new();
 
-    for key in app.pairs.keys() {
-        list_items.push(ListItem::new(Line::from(Span::styled(
-            format!("{: <25} : {}", key, app.pairs.get(key).unwrap()),
+    for row in app.rows() {
+        let marker = match row.value {
+            Value::Object(_) | Value::Array(_)
+                if app.search.is_empty() && app.collapsed.contains(&row.path) =>
+            {
+                "▸ "
+            }
+            Value::Object(_) | Value::Array(_) => "▾ ",
+            _ => "  ",
+        };
+        let mut spans = vec![Span::raw(format!("{}{marker}", "  ".repeat(row.depth)))];
+        spans.extend(highlight(
+            row.label(),
             Style::default().fg(Color::Yellow),
-        ))));
+            &app.search,
+        ));
+        spans.push(Span::raw(" : "));
+        spans.extend(highlight(
+            tree::summary(row.value),
+            value_style(row.value),
+            &app.search,
+        ));
+        list_items.push(ListItem::new(Line::from(spans)));
     }
 
-    let list = List::new(list_items);
+    let mut scrollbar_state = ScrollbarState::new(list_items.len())
+        .position(app.list_state.selected().unwrap_or_default());
+
+    let list = List::new(list_items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
 
-    frame.render_widget(list, chunks[1]);
+    frame.render_stateful_widget(list, list_area, &mut app.list_state);
+    frame.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(Some("↑"))
+            .end_symbol(Some("↓")),
+        scrollbar_area,
+        &mut scrollbar_state,
+    );
+
+    if searching || !app.search.is_empty() {
+        let match_count = app.matches().len();
+        let search_line = Line::from(vec![
+            Span::styled("/", Style::default().fg(Color::Cyan)),
+            Span::raw(app.search.clone()),
+            Span::styled(
+                format!("  ({match_count} matches)"),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(search_line), search_area);
+        if searching {
+            let width = app.search.width() as u16 + 1;
+            frame.set_cursor_position(Position::new(search_area.x + width, search_area.y));
+        }
+    }
     // ANCHOR_END: key_value_list
     // ANCHOR: lower_navigation_current_screen
     let current_navigation_text = vec![
@@ -59,6 +146,12 @@ pub fn ui(frame: &mut Frame, app: &App) {
             CurrentScreen::Editing => {
                 Span::styled("Editing Mode", Style::default().fg(Color::Yellow))
             }
+            CurrentScreen::Deleting => {
+                Span::styled("Deleting", Style::default().fg(Color::LightRed))
+            }
+            CurrentScreen::Searching => {
+                Span::styled("Search Mode", Style::default().fg(Color::Cyan))
+            }
             CurrentScreen::Exiting => Span::styled("Exiting", Style::default().fg(Color::LightRed)),
         }
         .to_owned(),
@@ -88,16 +181,41 @@ pub fn ui(frame: &mut Frame, app: &App) {
     // ANCHOR: lower_navigation_key_hint
     let current_keys_hint = {
         match app.current_screen {
-            CurrentScreen::Main => Span::styled(
-                "(q) to quit / (e) to make new pair",
+            CurrentScreen::Main => {
+                let mut keys = vec![
+                    "(q) quit",
+                    "(e) new pair",
+                    "(enter) edit",
+                    "(d) delete",
+                    "(space) fold",
+                    "(s) sort",
+                    "(/) search",
+                ];
+                if !app.search.is_empty() {
+                    keys.push("(n/N) next/previous match");
+                }
+                if app.path.is_some() {
+                    keys.push("(ctrl-s) save");
+                }
+                Span::styled(keys.join(" / "), Style::default().fg(Color::Red))
+            }
+            CurrentScreen::Editing => Span::styled(
+                "(ESC) to cancel/(Tab) to switch boxes/(↑↓) to change type/enter to complete",
                 Style::default().fg(Color::Red),
             ),
-            CurrentScreen::Editing => Span::styled(
-                "(ESC) to cancel/(Tab) to switch boxes/enter to complete",
+            CurrentScreen::Deleting => {
+                Span::styled("(y) yes / (n) no", Style::default().fg(Color::Red))
+            }
+            CurrentScreen::Searching => Span::styled(
+                "(enter) to keep the filter / (ESC) to clear it",
+                Style::default().fg(Color::Red),
+            ),
+            CurrentScreen::Exiting if app.path.is_none() => Span::styled(
+                "(←→) to choose a format / (y) yes / (n) no / (ESC) to go back",
                 Style::default().fg(Color::Red),
             ),
             CurrentScreen::Exiting => Span::styled(
-                "(q) to quit / (e) to make new pair",
+                "(y) yes / (n) no / (ESC) to go back",
                 Style::default().fg(Color::Red),
             ),
         }
@@ -121,8 +239,13 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
     // ANCHOR: editing_popup
     if let Some(editing) = &app.currently_editing {
+        let title = match app.editing.as_ref().and_then(|path| path.last()) {
+            Some(PathSegment::Key(key)) => format!("Edit `{key}`"),
+            Some(PathSegment::Index(index)) => format!("Edit item {index}"),
+            None => "Enter a new key-value pair".to_string(),
+        };
         let popup_block = Block::default()
-            .title("Enter a new key-value pair")
+            .title(title)
             .borders(Borders::NONE)
             .style(Style::default().bg(Color::DarkGray));
 
@@ -131,16 +254,26 @@ pub fn ui(frame: &mut Frame, app: &App) {
         // ANCHOR_END: editing_popup
 
         // ANCHOR: popup_layout
+        let popup_rows = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .split(area);
         let popup_chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .margin(1)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(area);
+            .split(popup_rows[0]);
         // ANCHOR_END: popup_layout
 
         // ANCHOR: key_value_blocks
         let mut key_block = Block::default().title("Key").borders(Borders::ALL);
-        let mut value_block = Block::default().title("Value").borders(Borders::ALL);
+        let mut value_block = Block::default()
+            .title(format!("Value ({})", app.value_type))
+            .borders(Borders::ALL);
 
         let active_style = Style::default().bg(Color::LightYellow).fg(Color::Black);
 
@@ -149,11 +282,59 @@ pub fn ui(frame: &mut Frame, app: &App) {
             CurrentlyEditing::Value => value_block = value_block.style(active_style),
         };
 
-        let key_text = Paragraph::new(app.key_input.clone()).block(key_block);
-        frame.render_widget(key_text, popup_chunks[0]);
+        let key_area = key_block.inner(popup_chunks[0]);
+        let value_area = value_block.inner(popup_chunks[1]);
+
+        let key_text = if app.parent_is_array() {
+            Paragraph::new("(array item)").style(Style::default().fg(Color::Gray))
+        } else {
+            text_field(&app.key_input, key_area)
+        };
+        frame.render_widget(key_text.block(key_block), popup_chunks[0]);
 
-        let value_text = Paragraph::new(app.value_input.clone()).block(value_block);
-        frame.render_widget(value_text, popup_chunks[1]);
+        let value_text = if app.value_type.has_input() {
+            text_field(&app.value_input, value_area)
+        } else {
+            let empty = app.value_type.parse("").unwrap_or_default();
+            Paragraph::new(tree::summary(&empty)).style(Style::default().fg(Color::Gray))
+        };
+        frame.render_widget(value_text.block(value_block), popup_chunks[1]);
+
+        // only the field being typed into shows its cursor
+        let active_field = match editing {
+            CurrentlyEditing::Key if !app.parent_is_array() => Some((&app.key_input, key_area)),
+            CurrentlyEditing::Value if app.value_type.has_input() => {
+                Some((&app.value_input, value_area))
+            }
+            _ => None,
+        };
+        if let Some((field, area)) = active_field {
+            let x = field.cursor_column() - field.scroll(area.width);
+            frame.set_cursor_position(Position::new(area.x + x, area.y));
+        }
+
+        let type_line = Line::from(vec![
+            Span::raw("Type: "),
+            Span::styled(
+                app.value_type.to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(type_line), popup_rows[1]);
+
+        if let Some(error) = app.value_error() {
+            let error = Paragraph::new(error).style(Style::default().fg(Color::LightRed));
+            frame.render_widget(error, popup_rows[2]);
+        } else if app.is_duplicate_key() {
+            let key = app.key_input.value();
+            let warning = if app.overwrite_key.as_deref() == Some(key) {
+                format!("`{key}` already exists, press enter again to overwrite it")
+            } else {
+                format!("`{key}` already exists")
+            };
+            let warning = Paragraph::new(warning).style(Style::default().fg(Color::Yellow));
+            frame.render_widget(warning, popup_rows[2]);
+        }
     }
     // ANCHOR_END: key_value_blocks
 
@@ -165,10 +346,41 @@ pub fn ui(frame: &mut Frame, app: &App) {
             .borders(Borders::NONE)
             .style(Style::default().bg(Color::DarkGray));
 
-        let exit_text = Text::styled(
-            "Would you like to output the buffer as json? (y/n)",
-            Style::default().fg(Color::Red),
-        );
+        let mut exit_text = match &app.path {
+            Some(path) => Text::styled(
+                format!(
+                    "You have unsaved changes. Save them to {} as {}? (y/n)",
+                    path.display(),
+                    app.format
+                ),
+                Style::default().fg(Color::Red),
+            ),
+            None => {
+                let formats = Format::ALL.iter().flat_map(|format| {
+                    let style = if *format == app.format {
+                        Style::default().fg(Color::Black).bg(Color::LightYellow)
+                    } else {
+                        Style::default().fg(Color::Gray)
+                    };
+                    [Span::styled(format!(" {format} "), style), Span::raw(" ")]
+                });
+                Text::from(vec![
+                    Line::styled(
+                        "Would you like to output the buffer? (y/n)",
+                        Style::default().fg(Color::Red),
+                    ),
+                    Line::default(),
+                    Line::from(formats.collect::<Vec<_>>()),
+                ])
+            }
+        };
+        if let Some(message) = &app.message {
+            exit_text.push_line(Line::default());
+            exit_text.push_line(Line::styled(
+                message.clone(),
+                Style::default().fg(Color::LightRed),
+            ));
+        }
         // the `trim: false` will stop the text from being cut off when over the edge of the block
         let exit_paragraph = Paragraph::new(exit_text)
             .block(popup_block)
@@ -178,6 +390,72 @@ pub fn ui(frame: &mut Frame, app: &App) {
         frame.render_widget(exit_paragraph, area);
     }
     // ANCHOR_END: exit_screen
+
+    // ANCHOR: delete_screen
+    if let CurrentScreen::Deleting = app.current_screen {
+        let label = app
+            .selected_path()
+            .and_then(|path| path.last().cloned())
+            .map(|segment| match segment {
+                PathSegment::Key(key) => format!("`{key}`"),
+                PathSegment::Index(index) => format!("item {index}"),
+            })
+            .unwrap_or_default();
+        let popup_block = Block::default()
+            .title("Y/N")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::DarkGray));
+        let delete_paragraph = Paragraph::new(Text::styled(
+            format!("Delete {label} and everything in it? (y/n)"),
+            Style::default().fg(Color::Red),
+        ))
+        .block(popup_block)
+        .wrap(Wrap { trim: false });
+
+        let area = centered_rect(50, 20, frame.area());
+        frame.render_widget(Clear, area);
+        frame.render_widget(delete_paragraph, area);
+    }
+    // ANCHOR_END: delete_screen
+}
+
+// ANCHOR: text_field
+/// A paragraph showing the text of `field`, scrolled so that its cursor is inside `area`.
+fn text_field(field: &TextField, area: Rect) -> Paragraph<'_> {
+    Paragraph::new(field.value()).scroll((0, field.scroll(area.width)))
+}
+// ANCHOR_END: text_field
+
+// ANCHOR: highlight
+/// Splits `text` into spans, marking the occurrences of `query`.
+fn highlight(text: String, style: Style, query: &str) -> Vec<Span<'static>> {
+    let highlighted = Style::default().bg(Color::Yellow).fg(Color::Black);
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for range in search::find_matches(&text, query) {
+        if range.start > end {
+            spans.push(Span::styled(text[end..range.start].to_string(), style));
+        }
+        spans.push(Span::styled(text[range.clone()].to_string(), highlighted));
+        end = range.end;
+    }
+    if end < text.len() {
+        spans.push(Span::styled(text[end..].to_string(), style));
+    }
+    spans
+}
+// ANCHOR_END: highlight
+
+/// The color of a value in the tree view, by its type.
+fn value_style(value: &Value) -> Style {
+    let color = match value {
+        Value::String(_) => Color::Green,
+        Value::Number(_) => Color::Cyan,
+        Value::Bool(_) => Color::Magenta,
+        Value::Null => Color::DarkGray,
+        Value::Object(_) | Value::Array(_) => Color::Gray,
+    };
+    Style::default().fg(color)
 }
 
 // ANCHOR: centered_rect
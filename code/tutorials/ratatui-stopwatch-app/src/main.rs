@@ -1,10 +1,14 @@
 // ANCHOR: app
 use std::{
-    io::Stderr,
+    fs::{self, OpenOptions},
+    io::{Stderr, Write},
+    path::PathBuf,
     time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Local, TimeDelta};
 use color_eyre::eyre::{eyre, Result};
+use directories::ProjectDirs;
 use futures::{FutureExt, StreamExt};
 use itertools::Itertools;
 use ratatui::{
@@ -12,15 +16,17 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Style, Stylize},
     text::{Line, Span},
-    widgets::Paragraph,
+    widgets::{Block, Paragraph},
     Frame, Terminal,
 };
-use strum::EnumIs;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIs};
 use tui_big_text::BigText;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let mut app = StopwatchApp::default();
+    app.load_state();
     app.run().await
 }
 
@@ -31,11 +37,12 @@ pub enum Event {
     Key(crossterm::event::KeyEvent),
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumIs)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumIs, Serialize, Deserialize)]
 enum AppState {
     #[default]
     Stopped,
     Running,
+    Paused,
     Quitting,
 }
 
@@ -43,14 +50,323 @@ enum AppState {
 enum Message {
     StartOrSplit,
     Stop,
+    PauseOrResume,
+    Export,
+    ToggleExportFormat,
+    SwitchMode,
+    ToggleNotifications,
+    OpenPrompt,
+    PromptChar(char),
+    PromptBackspace,
+    PromptSubmit,
+    PromptCancel,
     Tick,
     Quit,
 }
 
+/// What the timer counts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumIs, Serialize, Deserialize)]
+enum Mode {
+    /// Counts up, recording splits.
+    #[default]
+    Stopwatch,
+    /// Counts down from a duration.
+    Countdown,
+    /// Counts down alternating work and break phases.
+    Pomodoro,
+}
+
+impl Mode {
+    fn next(self) -> Self {
+        match self {
+            Mode::Stopwatch => Mode::Countdown,
+            Mode::Countdown => Mode::Pomodoro,
+            Mode::Pomodoro => Mode::Stopwatch,
+        }
+    }
+}
+
+/// The work and break phases of a Pomodoro session. Every cycle is a work phase followed by a
+/// break, except for the last one, which ends the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct PomodoroConfig {
+    #[serde(with = "millis")]
+    work: Duration,
+    #[serde(with = "millis")]
+    rest: Duration,
+    cycles: u32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            work: Duration::from_secs(25 * 60),
+            rest: Duration::from_secs(5 * 60),
+            cycles: 4,
+        }
+    }
+}
+
+impl PomodoroConfig {
+    /// Parses `WORK/BREAK xCYCLES`, e.g. `25m/5m x4`. The number of cycles can be left out.
+    fn parse(input: &str) -> Option<Self> {
+        let (durations, cycles) = match input.split_once('x') {
+            Some((durations, cycles)) => (durations, cycles.trim().parse().ok()?),
+            None => (input, PomodoroConfig::default().cycles),
+        };
+        let (work, rest) = durations.split_once('/')?;
+        let config = Self {
+            work: parse_duration(work)?,
+            rest: parse_duration(rest)?,
+            cycles,
+        };
+        (config.cycles > 0).then_some(config)
+    }
+
+    fn phases(&self) -> usize {
+        self.cycles as usize * 2 - 1
+    }
+
+    fn phase_duration(&self, phase: usize) -> Duration {
+        if phase.is_multiple_of(2) {
+            self.work
+        } else {
+            self.rest
+        }
+    }
+}
+
+/// Parses a duration such as `90s`, `1h30m`, `25` (minutes) or `1:30` (minutes and seconds).
+fn parse_duration(input: &str) -> Option<Duration> {
+    let input = input.trim();
+    if input.contains(':') {
+        let seconds = input.split(':').try_fold(0, |total, part| {
+            Some(total * 60 + part.parse::<u64>().ok()?)
+        })?;
+        return Some(Duration::from_secs(seconds)).filter(|d| !d.is_zero());
+    }
+    if let Ok(minutes) = input.parse::<u64>() {
+        return Some(Duration::from_secs(minutes * 60)).filter(|d| !d.is_zero());
+    }
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in input.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' => {
+                let unit = match c {
+                    'h' => 3600,
+                    'm' => 60,
+                    _ => 1,
+                };
+                seconds += number.parse::<u64>().ok()? * unit;
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(Duration::from_secs(seconds)).filter(|d| !d.is_zero())
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn toggle(self) -> Self {
+        match self {
+            ExportFormat::Csv => ExportFormat::Json,
+            ExportFormat::Json => ExportFormat::Csv,
+        }
+    }
+}
+
+/// A time during which the stopwatch was paused.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Pause {
+    /// The time counted when the stopwatch was paused.
+    #[serde(rename = "at_ms", with = "millis")]
+    at: Duration,
+    started: DateTime<Local>,
+    /// When the stopwatch was resumed, or `None` while it is still paused.
+    ended: Option<DateTime<Local>>,
+}
+
+impl Pause {
+    fn length(&self) -> Duration {
+        let ended = self.ended.unwrap_or_else(Local::now);
+        (ended - self.started).to_std().unwrap_or_default()
+    }
+}
+
+/// A lap between two splits.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Lap {
+    number: usize,
+    /// The wall-clock time at which the lap ended.
+    timestamp: DateTime<Local>,
+    #[serde(rename = "lap_ms", with = "millis")]
+    lap: Duration,
+    /// The time counted since the start of the session.
+    #[serde(rename = "total_ms", with = "millis")]
+    total: Duration,
+    /// How much slower the lap was than the best lap.
+    #[serde(rename = "delta_ms", with = "millis")]
+    delta: Duration,
+    /// The pauses taken during the lap, which are not counted in its time.
+    pauses: Vec<Pause>,
+}
+
+impl Lap {
+    fn paused(&self) -> Duration {
+        self.pauses.iter().map(Pause::length).sum()
+    }
+}
+
+/// A timed session, as it is exported and written to the history file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Session {
+    started: DateTime<Local>,
+    laps: Vec<Lap>,
+}
+
+impl Session {
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("lap,timestamp,lap_ms,total_ms,delta_ms,paused_ms\n");
+        for lap in &self.laps {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                lap.number,
+                lap.timestamp.to_rfc3339(),
+                lap.lap.as_millis(),
+                lap.total.as_millis(),
+                lap.delta.as_millis(),
+                lap.paused().as_millis()
+            ));
+        }
+        csv
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// The indexes of the fastest and the slowest lap, if there is more than one lap to compare.
+    fn best_and_worst(&self) -> Option<(usize, usize)> {
+        if self.laps.len() < 2 {
+            return None;
+        }
+        let best = self.laps.iter().position_min_by_key(|lap| lap.lap)?;
+        let worst = self.laps.iter().position_max_by_key(|lap| lap.lap)?;
+        Some((best, worst))
+    }
+}
+
+/// The directory for the history and the saved state, which is created if needed.
+fn data_dir() -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("com", "ratatui", "stopwatch")
+        .ok_or(eyre!("Unable to find the user data directory"))?;
+    fs::create_dir_all(dirs.data_dir())?;
+    Ok(dirs.data_dir().to_path_buf())
+}
+
+/// (De)serializes durations as a number of milliseconds.
+mod millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u128(duration.as_millis())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+/// (De)serializes lists of durations as numbers of milliseconds.
+mod millis_vec {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        durations: &[Duration],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(durations.iter().map(Duration::as_millis))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Duration>, D::Error> {
+        let millis = Vec::<u64>::deserialize(deserializer)?;
+        Ok(millis.into_iter().map(Duration::from_millis).collect())
+    }
+}
+
+/// The state that is saved to the user data directory, so that the stopwatch keeps running
+/// across restarts of the app.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SavedState {
+    state: AppState,
+    mode: Mode,
+    started: DateTime<Local>,
+    /// The time counted when the state was saved.
+    #[serde(with = "millis")]
+    elapsed: Duration,
+    saved: DateTime<Local>,
+    #[serde(with = "millis_vec")]
+    splits: Vec<Duration>,
+    pauses: Vec<Pause>,
+    #[serde(with = "millis")]
+    countdown: Duration,
+    pomodoro: PomodoroConfig,
+    phase: usize,
+    #[serde(with = "millis")]
+    phase_started: Duration,
+    export_format: ExportFormat,
+    notify: bool,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 struct StopwatchApp {
     state: AppState,
-    splits: Vec<Instant>,
+    /// The time counted at each split, starting with zero.
+    splits: Vec<Duration>,
+    pauses: Vec<Pause>,
+    /// The time counted before the stopwatch was last started or resumed.
+    accumulated: Duration,
+    /// When the stopwatch was last started or resumed.
+    resumed: Instant,
+    /// The wall-clock time of the first split.
+    started: DateTime<Local>,
+    export_format: ExportFormat,
+    /// The result of the last export or history write.
+    status: Option<String>,
+    mode: Mode,
+    countdown: Duration,
+    pomodoro: PomodoroConfig,
+    /// The index of the current Pomodoro phase, where even phases are work and odd ones breaks.
+    phase: usize,
+    /// The time counted when the current countdown or Pomodoro phase started.
+    phase_started: Duration,
+    /// The time left of the current countdown or Pomodoro phase.
+    remaining: Duration,
+    /// The text typed into the duration prompt, while it is open.
+    prompt: Option<String>,
+    /// Whether a desktop notification is sent when a countdown ends.
+    notify: bool,
+    /// When the screen stops flashing after a countdown ended.
+    flash_until: Option<Instant>,
     start_time: Instant,
     frames: u32,
     fps: f64,
@@ -69,6 +385,21 @@ impl StopwatchApp {
             frames: Default::default(),
             fps: Default::default(),
             splits: Default::default(),
+            pauses: Default::default(),
+            accumulated: Default::default(),
+            resumed: Instant::now(),
+            started: Local::now(),
+            export_format: Default::default(),
+            status: Default::default(),
+            mode: Default::default(),
+            countdown: Duration::from_secs(5 * 60),
+            pomodoro: Default::default(),
+            phase: Default::default(),
+            phase_started: Default::default(),
+            remaining: Duration::from_secs(5 * 60),
+            prompt: Default::default(),
+            notify: Default::default(),
+            flash_until: Default::default(),
             state: Default::default(),
         }
     }
@@ -88,12 +419,25 @@ impl StopwatchApp {
 
     fn handle_event(&self, event: Event) -> Result<Message> {
         let msg = match event {
+            Event::Key(key) if self.prompt.is_some() => match key.code {
+                crossterm::event::KeyCode::Char(c) => Message::PromptChar(c),
+                crossterm::event::KeyCode::Backspace => Message::PromptBackspace,
+                crossterm::event::KeyCode::Enter => Message::PromptSubmit,
+                crossterm::event::KeyCode::Esc => Message::PromptCancel,
+                _ => Message::Tick,
+            },
             Event::Key(key) => match key.code {
                 crossterm::event::KeyCode::Char('q') => Message::Quit,
                 crossterm::event::KeyCode::Char(' ') => Message::StartOrSplit,
                 crossterm::event::KeyCode::Char('s') | crossterm::event::KeyCode::Enter => {
                     Message::Stop
                 }
+                crossterm::event::KeyCode::Char('p') => Message::PauseOrResume,
+                crossterm::event::KeyCode::Char('e') => Message::Export,
+                crossterm::event::KeyCode::Char('f') => Message::ToggleExportFormat,
+                crossterm::event::KeyCode::Char('m') => Message::SwitchMode,
+                crossterm::event::KeyCode::Char('n') => Message::ToggleNotifications,
+                crossterm::event::KeyCode::Char('d') => Message::OpenPrompt,
                 _ => Message::Tick,
             },
             _ => Message::Tick,
@@ -105,26 +449,303 @@ impl StopwatchApp {
         match message {
             Message::StartOrSplit => self.start_or_split(),
             Message::Stop => self.stop(),
+            Message::PauseOrResume => self.pause_or_resume(),
+            Message::Export => self.export(),
+            Message::ToggleExportFormat => self.export_format = self.export_format.toggle(),
+            Message::SwitchMode => self.switch_mode(),
+            Message::ToggleNotifications => self.notify = !self.notify,
+            Message::OpenPrompt => self.open_prompt(),
+            Message::PromptChar(c) => self.prompt.iter_mut().for_each(|prompt| prompt.push(c)),
+            Message::PromptBackspace => {
+                self.prompt.iter_mut().for_each(|prompt| {
+                    prompt.pop();
+                });
+            }
+            Message::PromptSubmit => self.submit_prompt(),
+            Message::PromptCancel => self.prompt = None,
             Message::Tick => self.tick(),
             Message::Quit => self.quit(),
         }
+        // save on transitions only: quitting and the end of a countdown save by themselves, and
+        // settings are saved with the next transition
+        if matches!(
+            message,
+            Message::StartOrSplit
+                | Message::Stop
+                | Message::PauseOrResume
+                | Message::SwitchMode
+                | Message::PromptSubmit
+        ) {
+            self.save_state();
+        }
         Ok(())
     }
 
     fn start_or_split(&mut self) {
-        if self.state.is_stopped() {
-            self.start();
-        } else {
-            self.record_split();
+        match self.state {
+            AppState::Stopped => self.start(),
+            AppState::Paused => self.resume(),
+            _ if self.mode.is_stopwatch() => self.record_split(),
+            _ => {}
+        }
+    }
+
+    fn pause_or_resume(&mut self) {
+        match self.state {
+            AppState::Running => self.pause(),
+            AppState::Paused => self.resume(),
+            _ => {}
+        }
+    }
+
+    fn pause(&mut self) {
+        self.accumulated = self.elapsed();
+        self.pauses.push(Pause {
+            at: self.accumulated,
+            started: Local::now(),
+            ended: None,
+        });
+        self.state = AppState::Paused;
+    }
+
+    fn resume(&mut self) {
+        if let Some(pause) = self.pauses.last_mut() {
+            pause.ended.get_or_insert_with(Local::now);
+        }
+        self.resumed = Instant::now();
+        self.state = AppState::Running;
+    }
+
+    fn switch_mode(&mut self) {
+        if !self.state.is_stopped() {
+            return;
+        }
+        self.mode = self.mode.next();
+        self.phase = 0;
+        self.remaining = self.phase_duration();
+        self.status = None;
+    }
+
+    /// Opens the prompt for the countdown duration or the Pomodoro phases.
+    fn open_prompt(&mut self) {
+        if self.state.is_stopped() && !self.mode.is_stopwatch() {
+            self.prompt = Some(String::new());
+        }
+    }
+
+    fn submit_prompt(&mut self) {
+        let Some(input) = self.prompt.take() else {
+            return;
+        };
+        match self.mode {
+            Mode::Stopwatch => {}
+            Mode::Countdown => match parse_duration(&input) {
+                Some(duration) => self.countdown = duration,
+                None => self.status = Some(format!("Invalid duration: {input}")),
+            },
+            Mode::Pomodoro => match PomodoroConfig::parse(&input) {
+                Some(config) => self.pomodoro = config,
+                None => self.status = Some(format!("Invalid phases: {input}")),
+            },
+        }
+        self.phase = 0;
+        self.remaining = self.phase_duration();
+    }
+
+    /// The length of the current countdown or Pomodoro phase.
+    fn phase_duration(&self) -> Duration {
+        match self.mode {
+            Mode::Stopwatch => Duration::ZERO,
+            Mode::Countdown => self.countdown,
+            Mode::Pomodoro => self.pomodoro.phase_duration(self.phase),
         }
     }
 
     fn stop(&mut self) {
+        if self.state.is_stopped() {
+            return;
+        }
+        if self.state.is_paused() {
+            self.resume();
+        }
+        if !self.mode.is_stopwatch() {
+            self.state = AppState::Stopped;
+            self.phase = 0;
+            self.remaining = self.phase_duration();
+            return;
+        }
         self.record_split();
+        self.accumulated = self.elapsed();
         self.state = AppState::Stopped;
+        self.status = match self.append_history() {
+            Ok(()) => None,
+            Err(err) => Some(format!("Unable to save history: {err}")),
+        };
+    }
+
+    /// The laps recorded so far, timed against the fastest one.
+    fn session(&self) -> Session {
+        let durations: Vec<Duration> = self
+            .splits
+            .iter()
+            .tuple_windows()
+            .map(|(previous, current)| *current - *previous)
+            .collect();
+        let best = durations.iter().min().copied().unwrap_or_default();
+        let laps = self
+            .splits
+            .iter()
+            .skip(1)
+            .zip(durations)
+            .enumerate()
+            .map(|(index, (&total, lap))| {
+                let pauses_before: Duration = self
+                    .pauses
+                    .iter()
+                    .filter(|pause| pause.at < total)
+                    .map(Pause::length)
+                    .sum();
+                let pauses = self
+                    .pauses
+                    .iter()
+                    .filter(|pause| total - lap <= pause.at && pause.at < total)
+                    .cloned()
+                    .collect();
+                Lap {
+                    number: index + 1,
+                    timestamp: self.started
+                        + TimeDelta::from_std(total + pauses_before).unwrap_or_default(),
+                    lap,
+                    total,
+                    delta: lap - best,
+                    pauses,
+                }
+            })
+            .collect();
+        Session {
+            started: self.started,
+            laps,
+        }
+    }
+
+    /// Writes the session to a file named after its start time in the current directory.
+    fn export(&mut self) {
+        let session = self.session();
+        if session.laps.is_empty() {
+            self.status = Some("Nothing to export yet".to_string());
+            return;
+        }
+        let path = PathBuf::from(format!(
+            "stopwatch-{}.{}",
+            session.started.format("%Y%m%d-%H%M%S"),
+            self.export_format
+        ));
+        let contents = match self.export_format {
+            ExportFormat::Csv => Ok(session.to_csv()),
+            ExportFormat::Json => session.to_json(),
+        };
+        let result = contents.and_then(|contents| Ok(fs::write(&path, contents)?));
+        self.status = Some(match result {
+            Ok(()) => format!("Exported to {}", path.display()),
+            Err(err) => format!("Unable to export: {err}"),
+        });
+    }
+
+    /// Appends the session as a line of JSON to `history.jsonl` in the user data directory.
+    fn append_history(&self) -> Result<()> {
+        let session = self.session();
+        if session.laps.is_empty() {
+            return Ok(());
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(data_dir()?.join("history.jsonl"))?;
+        writeln!(file, "{}", serde_json::to_string(&session)?)?;
+        Ok(())
+    }
+
+    fn saved_state(&self) -> SavedState {
+        SavedState {
+            state: self.state,
+            mode: self.mode,
+            started: self.started,
+            elapsed: self.elapsed(),
+            saved: Local::now(),
+            splits: self.splits.clone(),
+            pauses: self.pauses.clone(),
+            countdown: self.countdown,
+            pomodoro: self.pomodoro,
+            phase: self.phase,
+            phase_started: self.phase_started,
+            export_format: self.export_format,
+            notify: self.notify,
+        }
+    }
+
+    /// Continues from a saved state. A stopwatch that was running has kept counting since the
+    /// state was saved.
+    fn restore(&mut self, saved: SavedState) {
+        let since_saved = (Local::now() - saved.saved).to_std().unwrap_or_default();
+        self.state = match saved.state {
+            AppState::Quitting => AppState::Stopped,
+            state => state,
+        };
+        self.accumulated = if self.state.is_running() {
+            saved.elapsed + since_saved
+        } else {
+            saved.elapsed
+        };
+        self.resumed = Instant::now();
+        self.mode = saved.mode;
+        self.started = saved.started;
+        self.splits = saved.splits;
+        self.pauses = saved.pauses;
+        self.countdown = saved.countdown;
+        // the file may have been edited by hand, and a Pomodoro needs at least one cycle
+        self.pomodoro = if saved.pomodoro.cycles > 0 {
+            saved.pomodoro
+        } else {
+            PomodoroConfig::default()
+        };
+        self.phase = saved.phase;
+        self.phase_started = saved.phase_started;
+        self.export_format = saved.export_format;
+        self.notify = saved.notify;
+        self.remaining = self
+            .phase_duration()
+            .saturating_sub(self.accumulated.saturating_sub(self.phase_started));
+    }
+
+    /// Writes the state to `state.json` in the user data directory.
+    fn save_state(&mut self) {
+        let result = data_dir().and_then(|dir| {
+            let state = serde_json::to_string_pretty(&self.saved_state())?;
+            Ok(fs::write(dir.join("state.json"), state)?)
+        });
+        if let Err(err) = result {
+            self.status = Some(format!("Unable to save state: {err}"));
+        }
+    }
+
+    /// Restores the state saved by the last run of the app, if there is one.
+    fn load_state(&mut self) {
+        let result = data_dir().and_then(|dir| {
+            let path = dir.join("state.json");
+            if !path.exists() {
+                return Ok(None);
+            }
+            Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+        });
+        match result {
+            Ok(Some(saved)) => self.restore(saved),
+            Ok(None) => {}
+            Err(err) => self.status = Some(format!("Unable to load state: {err}")),
+        }
     }
 
     fn tick(&mut self) {
+        self.count_down();
         self.frames += 1;
         let now = Instant::now();
         let elapsed = (now - self.start_time).as_secs_f64();
@@ -135,12 +756,82 @@ impl StopwatchApp {
         }
     }
 
+    /// Updates the time left, moving on to the next Pomodoro phase or stopping when it runs out.
+    fn count_down(&mut self) {
+        if !self.state.is_running() || self.mode.is_stopwatch() {
+            return;
+        }
+        let elapsed = self.elapsed().saturating_sub(self.phase_started);
+        self.remaining = self.phase_duration().saturating_sub(elapsed);
+        if !self.remaining.is_zero() {
+            return;
+        }
+        match self.mode {
+            Mode::Pomodoro if self.phase + 1 < self.pomodoro.phases() => {
+                self.phase_started += self.phase_duration();
+                self.phase += 1;
+                self.remaining = self.phase_duration();
+                let message = if self.phase.is_multiple_of(2) {
+                    "Back to work"
+                } else {
+                    "Time for a break"
+                };
+                self.alert(message);
+            }
+            Mode::Pomodoro => {
+                self.state = AppState::Stopped;
+                self.alert("Pomodoro finished");
+            }
+            _ => {
+                self.state = AppState::Stopped;
+                self.alert("Countdown finished");
+            }
+        }
+        // otherwise a restart would restore the countdown as running and ring it again
+        self.save_state();
+    }
+
+    /// Rings the terminal bell, flashes the screen and, if enabled, sends a desktop notification
+    /// through the OSC 9 and OSC 777 escape sequences that many terminals understand.
+    fn alert(&mut self, message: &str) {
+        self.flash_until = Some(Instant::now() + Duration::from_secs(2));
+        self.status = Some(message.to_string());
+        let mut sequence = String::from("\x07");
+        if self.notify {
+            sequence.push_str(&format!("\x1b]9;{message}\x07"));
+            sequence.push_str(&format!("\x1b]777;notify;Stopwatch;{message}\x07"));
+        }
+        let mut stderr = std::io::stderr();
+        if let Err(err) = stderr
+            .write_all(sequence.as_bytes())
+            .and_then(|()| stderr.flush())
+        {
+            log::error!("Unable to ring the bell: {err}");
+        }
+    }
+
+    fn is_flashing(&self) -> bool {
+        self.flash_until.is_some_and(|until| {
+            let now = Instant::now();
+            now < until && ((until - now).as_millis() / 250).is_multiple_of(2)
+        })
+    }
+
     fn quit(&mut self) {
+        self.save_state();
         self.state = AppState::Quitting
     }
 
     fn start(&mut self) {
         self.splits.clear();
+        self.pauses.clear();
+        self.accumulated = Duration::ZERO;
+        self.resumed = Instant::now();
+        self.started = Local::now();
+        self.status = None;
+        self.phase = 0;
+        self.phase_started = Duration::ZERO;
+        self.remaining = self.phase_duration();
         self.state = AppState::Running;
         self.record_split();
     }
@@ -149,28 +840,38 @@ impl StopwatchApp {
         if !self.state.is_running() {
             return;
         }
-        self.splits.push(Instant::now());
+        self.splits.push(self.elapsed());
     }
 
-    fn elapsed(&mut self) -> Duration {
+    /// The time counted so far, which leaves out the pauses.
+    fn elapsed(&self) -> Duration {
         if self.state.is_running() {
-            self.splits.first().map_or(Duration::ZERO, Instant::elapsed)
+            self.accumulated + self.resumed.elapsed()
         } else {
-            // last - first or 0 if there are no splits
-            let now = Instant::now();
-            let first = *self.splits.first().unwrap_or(&now);
-            let last = *self.splits.last().unwrap_or(&now);
-            last - first
+            self.accumulated
         }
     }
 
     fn ui(&mut self, f: &mut Frame) -> Result<()> {
         let layout = self.layout(f.area());
-        f.render_widget(Paragraph::new("Stopwatch Example"), layout[0]);
+        if self.is_flashing() {
+            f.render_widget(Block::new().on_yellow(), f.area());
+        }
+        f.render_widget(Paragraph::new(self.title()), layout[0]);
         f.render_widget(self.fps_paragraph(), layout[1]);
         f.render_widget(self.timer_paragraph(), layout[2]);
-        f.render_widget(Paragraph::new("Splits:"), layout[3]);
-        f.render_widget(self.splits_paragraph(), layout[4]);
+        f.render_widget(self.splits_header(), layout[3]);
+        if let Some(status) = &self.status {
+            f.render_widget(
+                Paragraph::new(status.as_str()).alignment(Alignment::Right),
+                layout[3],
+            );
+        }
+        if self.mode.is_stopwatch() {
+            f.render_widget(self.splits_paragraph(), layout[4]);
+        } else {
+            f.render_widget(self.settings_paragraph(), layout[4]);
+        }
         f.render_widget(self.help_paragraph(), layout[5]);
         Ok(())
     }
@@ -183,12 +884,16 @@ impl StopwatchApp {
     }
 
     fn timer_paragraph(&mut self) -> BigText<'_> {
-        let style = if self.state.is_running() {
-            Style::new().green()
+        let style = match self.state {
+            AppState::Running => Style::new().green(),
+            AppState::Paused => Style::new().yellow(),
+            _ => Style::new().red(),
+        };
+        let elapsed = if self.mode.is_stopwatch() {
+            self.elapsed()
         } else {
-            Style::new().red()
+            self.remaining
         };
-        let elapsed = self.elapsed();
         let duration = self.format_duration(elapsed);
         let lines = vec![duration.into()];
         tui_big_text::BigTextBuilder::default()
@@ -197,40 +902,122 @@ impl StopwatchApp {
             .build()
     }
 
-    /// Renders the splits as a list of lines.
+    fn title(&self) -> String {
+        match self.mode {
+            Mode::Pomodoro => {
+                let kind = if self.phase.is_multiple_of(2) {
+                    "work"
+                } else {
+                    "break"
+                };
+                let cycle = self.phase / 2 + 1;
+                format!("Pomodoro {kind} {cycle}/{}", self.pomodoro.cycles)
+            }
+            mode => mode.to_string(),
+        }
+    }
+
+    fn splits_header(&self) -> Paragraph<'_> {
+        match (&self.prompt, self.mode) {
+            (Some(input), Mode::Pomodoro) => {
+                Paragraph::new(format!("Work/break xCycles: {input}_"))
+            }
+            (Some(input), _) => Paragraph::new(format!("Duration: {input}_")),
+            (None, Mode::Stopwatch) => Paragraph::new("Lap  Lap time    Total       Delta").bold(),
+            (None, _) => Paragraph::new("Settings:").bold(),
+        }
+    }
+
+    fn settings_paragraph(&self) -> Paragraph<'_> {
+        let settings = match self.mode {
+            Mode::Pomodoro => format!(
+                "{} work, {} break, {} cycles",
+                self.format_duration(self.pomodoro.work),
+                self.format_duration(self.pomodoro.rest),
+                self.pomodoro.cycles
+            ),
+            _ => format!("{} countdown", self.format_duration(self.countdown)),
+        };
+        let notifications = if self.notify { "on" } else { "off" };
+        Paragraph::new(vec![
+            settings.into(),
+            format!("Desktop notifications {notifications}").into(),
+        ])
+    }
+
+    /// Renders the laps as a list of lines, with the latest lap first and the fastest and slowest
+    /// laps highlighted.
     ///
     /// ```text
-    /// #01 -- 00:00.693 -- 00:00.693
-    /// #02 -- 00:00.719 -- 00:01.413
+    /// #02  00:00.719   00:01.413   +00:00.026   paused 1x for 00:03.120
+    /// #01  00:00.693   00:00.693   +00:00.000
     /// ```
     fn splits_paragraph(&mut self) -> Paragraph<'_> {
-        let start = *self.splits.first().unwrap_or(&Instant::now());
-        let mut splits = self
-            .splits
+        let session = self.session();
+        let best_and_worst = session.best_and_worst();
+        let mut laps = session
+            .laps
             .iter()
-            .copied()
-            .tuple_windows()
             .enumerate()
-            .map(|(index, (prev, current))| self.format_split(index, start, prev, current))
+            .map(|(index, lap)| {
+                let style = match best_and_worst {
+                    Some((best, _)) if best == index => Style::new().green(),
+                    Some((_, worst)) if worst == index => Style::new().red(),
+                    _ => Style::new().yellow(),
+                };
+                self.format_lap(lap, style)
+            })
             .collect::<Vec<_>>();
-        splits.reverse();
-        Paragraph::new(splits)
+        laps.reverse();
+        Paragraph::new(laps)
     }
 
     fn help_paragraph(&mut self) -> Paragraph<'_> {
-        let space_action = if self.state.is_stopped() {
-            "start"
+        if self.prompt.is_some() {
+            let help_text = Line::from(vec![
+                "enter ".into(),
+                "set".dim(),
+                " esc ".into(),
+                "cancel".dim(),
+            ]);
+            return Paragraph::new(help_text).gray();
+        }
+        let space_action = match self.state {
+            AppState::Stopped => "start",
+            AppState::Paused => "resume",
+            _ => "split",
+        };
+        let pause_action = if self.state.is_paused() {
+            "resume"
         } else {
-            "split"
+            "pause"
         };
-        let help_text = Line::from(vec![
+        let mut help_text = Line::from(vec![
             "space ".into(),
             space_action.dim(),
+            " p ".into(),
+            pause_action.dim(),
             " enter ".into(),
             "stop".dim(),
-            " q ".into(),
-            "quit".dim(),
+            " m ".into(),
+            "mode".dim(),
         ]);
+        if self.mode.is_stopwatch() {
+            help_text.extend([
+                " e ".into(),
+                format!("export {}", self.export_format).dim(),
+                " f ".into(),
+                "format".dim(),
+            ]);
+        } else {
+            help_text.extend([
+                " d ".into(),
+                "duration".dim(),
+                " n ".into(),
+                "notifications".dim(),
+            ]);
+        }
+        help_text.extend([" q ".into(), "quit".dim()]);
         Paragraph::new(help_text).gray()
     }
 
@@ -261,21 +1048,29 @@ impl StopwatchApp {
             .collect()
     }
 
-    fn format_split<'a>(
-        &self,
-        index: usize,
-        start: Instant,
-        previous: Instant,
-        current: Instant,
-    ) -> Line<'a> {
-        let split = self.format_duration(current - previous);
-        let elapsed = self.format_duration(current - start);
-        Line::from(vec![
-            format!("#{:02} -- ", index + 1).into(),
-            Span::styled(split, Style::new().yellow()),
-            " -- ".into(),
-            Span::styled(elapsed, Style::new()),
-        ])
+    fn format_lap<'a>(&self, lap: &Lap, style: Style) -> Line<'a> {
+        let mut line = Line::from(vec![
+            format!("#{:02}  ", lap.number).into(),
+            Span::styled(self.format_duration(lap.lap), style),
+            "   ".into(),
+            Span::styled(self.format_duration(lap.total), Style::new()),
+            "   ".into(),
+            Span::styled(
+                format!("+{}", self.format_duration(lap.delta)),
+                Style::new().dim(),
+            ),
+        ]);
+        if !lap.pauses.is_empty() {
+            line.push_span(Span::styled(
+                format!(
+                    "   paused {}x for {}",
+                    lap.pauses.len(),
+                    self.format_duration(lap.paused())
+                ),
+                Style::new().blue(),
+            ));
+        }
+        line
     }
 
     fn format_duration(&self, duration: Duration) -> String {
@@ -417,3 +1212,112 @@ impl Drop for Tui {
         self.exit().unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_times_laps_against_the_best() {
+        let mut app = StopwatchApp::new();
+        app.splits = [0, 700, 1300, 2200].map(Duration::from_millis).to_vec();
+        let session = app.session();
+        let laps: Vec<_> = session
+            .laps
+            .iter()
+            .map(|lap| {
+                (
+                    lap.lap.as_millis(),
+                    lap.total.as_millis(),
+                    lap.delta.as_millis(),
+                )
+            })
+            .collect();
+        assert_eq!(laps, [(700, 700, 100), (600, 1300, 0), (900, 2200, 300)]);
+        assert_eq!(session.best_and_worst(), Some((1, 2)));
+        assert_eq!(
+            session.laps[2].timestamp - session.started,
+            TimeDelta::milliseconds(2200)
+        );
+        let csv = session.to_csv();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(2).unwrap().ends_with(",600,1300,0,0"));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration(" 25 "), Some(Duration::from_secs(1500)));
+        assert_eq!(parse_duration("1:30"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("0"), None);
+        assert_eq!(parse_duration("5x"), None);
+        assert_eq!(
+            PomodoroConfig::parse("50m/10m x2"),
+            Some(PomodoroConfig {
+                work: Duration::from_secs(3000),
+                rest: Duration::from_secs(600),
+                cycles: 2,
+            })
+        );
+        assert_eq!(PomodoroConfig::parse("50m/10m x0"), None);
+    }
+
+    #[test]
+    fn test_session_marks_laps_with_their_pauses() {
+        let mut app = StopwatchApp::new();
+        app.splits = [0, 700, 1300, 2200].map(Duration::from_millis).to_vec();
+        app.pauses = vec![Pause {
+            at: Duration::from_millis(1000),
+            started: app.started + TimeDelta::milliseconds(1000),
+            ended: Some(app.started + TimeDelta::milliseconds(4000)),
+        }];
+        let session = app.session();
+        let paused: Vec<_> = session.laps.iter().map(|lap| lap.pauses.len()).collect();
+        assert_eq!(paused, [0, 1, 0]);
+        assert_eq!(session.laps[1].paused(), Duration::from_secs(3));
+        assert_eq!(
+            session.laps[2].timestamp - session.started,
+            TimeDelta::milliseconds(5200)
+        );
+    }
+
+    #[test]
+    fn test_restore_keeps_counting_while_closed() {
+        let mut app = StopwatchApp::new();
+        app.start();
+        let mut saved = app.saved_state();
+        saved.elapsed = Duration::from_secs(10);
+        saved.saved -= TimeDelta::seconds(5);
+        let mut restored = StopwatchApp::new();
+        restored.restore(saved.clone());
+        assert!(restored.elapsed() >= Duration::from_secs(15));
+
+        saved.state = AppState::Paused;
+        restored.restore(saved);
+        assert_eq!(restored.elapsed(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_restore_rejects_zero_cycles() {
+        let mut saved = StopwatchApp::new().saved_state();
+        saved.mode = Mode::Pomodoro;
+        saved.pomodoro.cycles = 0;
+        let mut restored = StopwatchApp::new();
+        restored.restore(saved);
+        assert_eq!(restored.pomodoro, PomodoroConfig::default());
+        assert_eq!(restored.pomodoro.phases(), 7);
+    }
+
+    #[test]
+    fn test_saved_state_uses_millis() {
+        let mut app = StopwatchApp::new();
+        app.splits = [0, 700, 1300].map(Duration::from_millis).to_vec();
+        let saved = app.saved_state();
+        let json = serde_json::to_value(&saved).unwrap();
+        assert_eq!(json["splits"], serde_json::json!([0, 700, 1300]));
+        assert_eq!(json["countdown"], 300_000);
+        assert_eq!(json["pomodoro"]["work"], 1_500_000);
+        assert_eq!(serde_json::from_value::<SavedState>(json).unwrap(), saved);
+    }
+}